@@ -8,6 +8,12 @@ pub struct Config {
     pub mongodb_uri: String,
     pub pubg_api_key: String,
     pub pubg_api_base_url: String,
+    pub pubg_api_rate_limit: u32,
+    pub pubg_api_rate_limit_matches: bool,
+    pub match_fetch_concurrency: usize,
+    pub stats_max_staleness_hours: i64,
+    pub telemetry_dir: String,
+    pub telemetry_retention_hours: u64,
     pub cors_origin: String,
     pub rust_log: String,
 }
@@ -25,6 +31,27 @@ impl Config {
             pubg_api_key: env::var("PUBG_API_KEY")?,
             pubg_api_base_url: env::var("PUBG_API_BASE_URL")
                 .unwrap_or_else(|_| "https://api.pubg.com/shards".to_string()),
+            pubg_api_rate_limit: env::var("PUBG_API_RATE_LIMIT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .expect("PUBG_API_RATE_LIMIT must be a number of requests per minute"),
+            pubg_api_rate_limit_matches: env::var("PUBG_API_RATE_LIMIT_MATCHES")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            match_fetch_concurrency: env::var("MATCH_FETCH_CONCURRENCY")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .expect("MATCH_FETCH_CONCURRENCY must be a number of matches"),
+            stats_max_staleness_hours: env::var("STATS_MAX_STALENESS_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .expect("STATS_MAX_STALENESS_HOURS must be a number of hours"),
+            telemetry_dir: env::var("TELEMETRY_DIR")
+                .unwrap_or_else(|_| "./data/telemetry".to_string()),
+            telemetry_retention_hours: env::var("TELEMETRY_RETENTION_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .expect("TELEMETRY_RETENTION_HOURS must be a number of hours"),
             cors_origin: env::var("CORS_ORIGIN").unwrap_or_else(|_| "*".to_string()),
             rust_log: env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
         })
@@ -1,11 +1,22 @@
 use mongodb::{
     bson::doc,
+    error::ErrorKind,
     options::{ClientOptions, IndexOptions},
     Client, Collection, Database, IndexModel,
 };
 use std::sync::Arc;
 
-use crate::models::{Player, PlayerStats};
+use crate::models::{
+    KillMatchRecord, KillRecord, LandingRecord, MatchRecord, MatchWeaponsRecord, Player, PlayerStats,
+    RankedStatsRecord, SeasonStatsRecord,
+};
+
+// Server error code of createIndex when an index of the same name has other options
+const INDEX_OPTIONS_CONFLICT: i32 = 85;
+
+// Live season and ranked stats are kept this long after they expire, so they can still be
+// served when PUBG fails to refresh them
+const SEASON_STATS_RETENTION: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 3600);
 
 #[derive(Clone)]
 pub struct MongoDb {
@@ -44,7 +55,41 @@ impl MongoDb {
         self.database.collection("player_stats")
     }
 
-    pub async fn create_indexes(&self) -> Result<(), mongodb::error::Error> {
+    pub fn matches(&self) -> Collection<MatchRecord> {
+        self.database.collection("matches")
+    }
+
+    pub fn season_stats(&self) -> Collection<SeasonStatsRecord> {
+        self.database.collection("season_stats")
+    }
+
+    pub fn ranked_stats(&self) -> Collection<RankedStatsRecord> {
+        self.database.collection("ranked_stats")
+    }
+
+    pub fn landings(&self) -> Collection<LandingRecord> {
+        self.database.collection("landings")
+    }
+
+    pub fn kills(&self) -> Collection<KillRecord> {
+        self.database.collection("kills")
+    }
+
+    pub fn kill_matches(&self) -> Collection<KillMatchRecord> {
+        self.database.collection("kill_matches")
+    }
+
+    pub fn match_weapons(&self) -> Collection<MatchWeaponsRecord> {
+        self.database.collection("match_weapons")
+    }
+
+    /// Creates the indexes. Stored stats are deleted `stats_retention` after they expire, so
+    /// they can still be served stale in the meantime. Live season and ranked stats are kept a
+    /// week past their expiry for the same reason.
+    pub async fn create_indexes(
+        &self,
+        stats_retention: std::time::Duration,
+    ) -> Result<(), mongodb::error::Error> {
         tracing::info!("Creating MongoDB indexes...");
 
         // Index unique sur account_id dans players
@@ -81,20 +126,137 @@ impl MongoDb {
         stats_collection.create_index(stats_index, None).await?;
 
         // Index TTL sur expires_at pour auto-delete des stats expirées
-        let ttl_index = IndexModel::builder()
-            .keys(doc! { "expires_at": 1 })
+        self.create_ttl_index(&stats_collection, "stats_ttl", stats_retention)
+            .await?;
+
+        // Index multikey sur les participants pour retrouver les matches d'un joueur
+        let matches_collection = self.matches();
+        let participants_index = IndexModel::builder()
+            .keys(doc! { "participant_ids": 1, "created_at": -1 })
+            .options(
+                IndexOptions::builder()
+                    .name("matches_participants".to_string())
+                    .build(),
+            )
+            .build();
+        matches_collection
+            .create_index(participants_index, None)
+            .await?;
+
+        // Index unique sur les stats officielles d'un joueur pour une saison
+        let season_stats_collection = self.season_stats();
+        let season_stats_index = IndexModel::builder()
+            .keys(doc! { "account_id": 1, "shard": 1, "season_id": 1 })
             .options(
                 IndexOptions::builder()
-                    .expire_after(std::time::Duration::from_secs(0))
-                    .name("stats_ttl".to_string())
+                    .unique(true)
+                    .name("season_stats_unique".to_string())
                     .build(),
             )
             .build();
-        stats_collection.create_index(ttl_index, None).await?;
+        season_stats_collection
+            .create_index(season_stats_index, None)
+            .await?;
+
+        // Index TTL, les saisons passées n'ont pas d'expires_at et sont conservées
+        self.create_ttl_index(
+            &season_stats_collection,
+            "season_stats_ttl",
+            SEASON_STATS_RETENTION,
+        )
+        .await?;
+
+        // Même organisation pour les stats classées
+        let ranked_stats_collection = self.ranked_stats();
+        let ranked_stats_index = IndexModel::builder()
+            .keys(doc! { "account_id": 1, "shard": 1, "season_id": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    .name("ranked_stats_unique".to_string())
+                    .build(),
+            )
+            .build();
+        ranked_stats_collection
+            .create_index(ranked_stats_index, None)
+            .await?;
+
+        self.create_ttl_index(
+            &ranked_stats_collection,
+            "ranked_stats_ttl",
+            SEASON_STATS_RETENTION,
+        )
+        .await?;
+
+        // Index sur le tueur et la victime pour retrouver les rivaux d'un joueur
+        let kills_collection = self.kills();
+        let kills_indexes = [
+            ("kills_match", doc! { "match_id": 1 }),
+            ("kills_killer", doc! { "killer.account_id": 1 }),
+            ("kills_victim", doc! { "victim.account_id": 1 }),
+        ];
+        for (name, keys) in kills_indexes {
+            let index = IndexModel::builder()
+                .keys(keys)
+                .options(IndexOptions::builder().name(name.to_string()).build())
+                .build();
+            kills_collection.create_index(index, None).await?;
+        }
+
+        // Index sur le joueur et le match pour retrouver les atterrissages
+        let landings_collection = self.landings();
+        let landings_indexes = [
+            ("landings_account", doc! { "account_id": 1 }),
+            ("landings_match", doc! { "match_id": 1 }),
+        ];
+        for (name, keys) in landings_indexes {
+            let index = IndexModel::builder()
+                .keys(keys)
+                .options(IndexOptions::builder().name(name.to_string()).build())
+                .build();
+            landings_collection.create_index(index, None).await?;
+        }
 
         tracing::info!("MongoDB indexes created successfully");
         Ok(())
     }
+
+    // TTL index deleting documents `expire_after` past their `expires_at`
+    async fn create_ttl_index<T>(
+        &self,
+        collection: &Collection<T>,
+        name: &str,
+        expire_after: std::time::Duration,
+    ) -> Result<(), mongodb::error::Error> {
+        let index = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(expire_after)
+                    .name(name.to_string())
+                    .build(),
+            )
+            .build();
+        if let Err(e) = collection.create_index(index, None).await {
+            // An existing index with another expiry (older retention) is updated in place
+            if !matches!(*e.kind, ErrorKind::Command(ref c) if c.code == INDEX_OPTIONS_CONFLICT) {
+                return Err(e);
+            }
+            self.database
+                .run_command(
+                    doc! {
+                        "collMod": collection.name(),
+                        "index": {
+                            "name": name,
+                            "expireAfterSeconds": expire_after.as_secs() as i64,
+                        },
+                    },
+                    None,
+                )
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 pub type SharedMongoDb = Arc<MongoDb>;
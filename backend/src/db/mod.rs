@@ -2,4 +2,7 @@ pub mod connection;
 pub mod repository;
 
 pub use connection::MongoDb;
-pub use repository::{PlayerRepository, StatsRepository};
+pub use repository::{
+    KillRepository, LandingRepository, MatchRepository, PlayerRepository, RankedStatsRepository,
+    SeasonStatsRepository, StatsRepository, WeaponRepository,
+};
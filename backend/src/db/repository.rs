@@ -1,9 +1,19 @@
+use chrono::{DateTime, Utc};
 use mongodb::{
-    bson::{doc, oid::ObjectId, to_bson},
+    bson::{doc, oid::ObjectId, to_bson, Document},
+    error::{BulkWriteFailure, ErrorKind},
+    options::{FindOptions, InsertManyOptions, ReplaceOptions, UpdateOptions},
     Collection,
 };
+use std::collections::HashSet;
 
-use crate::models::{Player, PlayerStats};
+use crate::models::{
+    KillMatchRecord, KillRecord, LandingRecord, MatchHistoryFilter, MatchRecord,
+    MatchWeaponsRecord, Player, PlayerStats, RankedStatsRecord, SeasonStatsRecord,
+};
+
+// Server error code of a write rejected by a unique index
+const DUPLICATE_KEY: i32 = 11000;
 
 pub struct PlayerRepository {
     collection: Collection<Player>,
@@ -34,6 +44,19 @@ impl PlayerRepository {
             .await
     }
 
+    pub async fn find_by_account_ids(
+        &self,
+        account_ids: &[String],
+    ) -> Result<Vec<Player>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let cursor = self
+            .collection
+            .find(doc! { "account_id": { "$in": account_ids } }, None)
+            .await?;
+        cursor.try_collect().await
+    }
+
     pub async fn find_all(&self) -> Result<Vec<Player>, mongodb::error::Error> {
         use futures::stream::TryStreamExt;
         
@@ -47,6 +70,7 @@ impl PlayerRepository {
                 "name": player.name,
                 "shard": player.shard,
                 "last_matches": player.last_matches,
+                "match_history": player.match_history,
                 "last_refreshed_at": player.last_refreshed_at,
                 "summary": to_bson(&player.summary).unwrap_or(mongodb::bson::Bson::Null),
             }
@@ -102,6 +126,8 @@ impl StatsRepository {
             .await
     }
 
+    /// Inserts or updates the stats of a player for a period, mode and shard in a single
+    /// operation. `_id` and the key fields are only written on insert.
     pub async fn upsert(&self, stats: PlayerStats) -> Result<(), mongodb::error::Error> {
         let filter = doc! {
             "player_id": stats.player_id,
@@ -110,24 +136,24 @@ impl StatsRepository {
             "shard": &stats.shard
         };
 
+        let mut values = mongodb::bson::to_document(&stats)?;
+        for key in ["_id", "player_id", "period", "mode", "shard"] {
+            values.remove(key);
+        }
+
         let update = doc! {
-            "$set": {
-                "kills": stats.kills,
-                "deaths": stats.deaths,
-                "kd_ratio": stats.kd_ratio,
-                "win_rate": stats.win_rate,
-                "damage_dealt": stats.damage_dealt,
-                "survival_time": stats.survival_time,
-                "top1_count": stats.top1_count,
-                "matches_played": stats.matches_played,
-                "computed_at": stats.computed_at,
-                "expires_at": stats.expires_at,
+            "$set": values,
+            "$setOnInsert": {
+                "_id": stats.id.unwrap_or_default(), // a new ObjectId
+                "player_id": stats.player_id,
+                "period": &stats.period,
+                "mode": &stats.mode,
+                "shard": &stats.shard,
             }
         };
 
-        self.collection
-            .update_one(filter, update, None)
-            .await?;
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.collection.update_one(filter, update, options).await?;
         Ok(())
     }
 
@@ -137,4 +163,495 @@ impl StatsRepository {
             .await?;
         Ok(())
     }
+
+    /// Deletes the stats of a player and/or a period, every stats when both are `None`.
+    pub async fn delete_matching(
+        &self,
+        player_id: Option<&ObjectId>,
+        period: Option<&str>,
+    ) -> Result<u64, mongodb::error::Error> {
+        let mut filter = doc! {};
+        if let Some(player_id) = player_id {
+            filter.insert("player_id", player_id);
+        }
+        if let Some(period) = period {
+            filter.insert("period", period);
+        }
+
+        let result = self.collection.delete_many(filter, None).await?;
+        Ok(result.deleted_count)
+    }
+}
+
+pub struct MatchRepository {
+    collection: Collection<MatchRecord>,
+}
+
+impl MatchRepository {
+    pub fn new(collection: Collection<MatchRecord>) -> Self {
+        MatchRepository { collection }
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<MatchRecord>, mongodb::error::Error> {
+        self.collection.find_one(doc! { "_id": id }, None).await
+    }
+
+    pub async fn find_by_ids(&self, ids: &[String]) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let cursor = self.collection.find(doc! { "_id": { "$in": ids } }, None).await?;
+        cursor.try_collect().await
+    }
+
+    /// Returns which of `ids` are already stored.
+    pub async fn find_stored_ids(&self, ids: &[String]) -> Result<HashSet<String>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+        let cursor = self
+            .collection
+            .clone_with_type::<Document>()
+            .find(doc! { "_id": { "$in": ids } }, options)
+            .await?;
+        let docs: Vec<Document> = cursor.try_collect().await?;
+
+        Ok(docs
+            .iter()
+            .filter_map(|d| d.get_str("_id").ok().map(str::to_string))
+            .collect())
+    }
+
+    /// The matches of `ids` that are stored, without their `data` document.
+    pub async fn find_headers_by_ids(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let options = FindOptions::builder().projection(doc! { "data": 0 }).build();
+        let cursor = self.collection.find(doc! { "_id": { "$in": ids } }, options).await?;
+        cursor.try_collect().await
+    }
+
+    /// Matches a player took part in, sorted by date, without their `data` document.
+    pub async fn find_headers_for_player(
+        &self,
+        account_id: &str,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        map_name: Option<&str>,
+        game_mode_pattern: Option<String>,
+        oldest_first: bool,
+    ) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let filter = player_matches_filter(&[account_id], from, to, map_name, game_mode_pattern);
+        let options = FindOptions::builder()
+            .projection(doc! { "data": 0 })
+            .sort(doc! { "created_at": if oldest_first { 1 } else { -1 } })
+            .build();
+
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    /// Matches any of the players took part in, within the optional dates, map and game
+    /// modes, without their `data` document.
+    pub async fn find_headers_for_players(
+        &self,
+        account_ids: &[&str],
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        map_name: Option<&str>,
+        game_mode_pattern: Option<String>,
+    ) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let filter = player_matches_filter(account_ids, from, to, map_name, game_mode_pattern);
+        let options = FindOptions::builder().projection(doc! { "data": 0 }).build();
+
+        let cursor = self.collection.find(filter, options).await?;
+        cursor.try_collect().await
+    }
+
+    /// One page of the player's stored matches passing the history filters, with the number
+    /// of matches passing them.
+    pub async fn find_history_page(
+        &self,
+        account_id: &str,
+        history: &MatchHistoryFilter,
+    ) -> Result<(u64, Vec<MatchRecord>), mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let filter = player_matches_filter(
+            &[account_id],
+            history.from,
+            history.to,
+            history.map.as_deref(),
+            history.mode.game_mode_pattern(),
+        );
+        let total = self.collection.count_documents(filter.clone(), None).await?;
+
+        let options = FindOptions::builder()
+            .sort(doc! { "created_at": if history.oldest_first { 1 } else { -1 } })
+            .skip((history.page.max(1) - 1) * history.page_size)
+            .limit(history.page_size as i64)
+            .build();
+        let cursor = self.collection.find(filter, options).await?;
+        Ok((total, cursor.try_collect().await?))
+    }
+
+    /// Stored matches any of the players took part in, played between `from` and `to` in the
+    /// game modes selected by `game_mode_pattern`.
+    pub async fn find_for_players(
+        &self,
+        account_ids: &[&str],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        game_mode_pattern: Option<String>,
+    ) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let filter = player_matches_filter(account_ids, Some(from), Some(to), None, game_mode_pattern);
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    /// Stored matches in which every one of `account_ids` took part, most recent first.
+    pub async fn find_with_participants(
+        &self,
+        account_ids: &[&str],
+    ) -> Result<Vec<MatchRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .build();
+
+        let cursor = self
+            .collection
+            .find(doc! { "participant_ids": { "$all": account_ids } }, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    pub async fn save(&self, record: &MatchRecord) -> Result<(), mongodb::error::Error> {
+        // Replace rather than insert so two concurrent fetches of the same match don't conflict
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.collection
+            .replace_one(doc! { "_id": &record.id }, record, options)
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct SeasonStatsRepository {
+    collection: Collection<SeasonStatsRecord>,
+}
+
+impl SeasonStatsRepository {
+    pub fn new(collection: Collection<SeasonStatsRecord>) -> Self {
+        SeasonStatsRepository { collection }
+    }
+
+    pub async fn find(
+        &self,
+        account_id: &str,
+        shard: &str,
+        season_id: &str,
+    ) -> Result<Option<SeasonStatsRecord>, mongodb::error::Error> {
+        self.collection
+            .find_one(
+                doc! { "account_id": account_id, "shard": shard, "season_id": season_id },
+                None,
+            )
+            .await
+    }
+
+    pub async fn save(&self, record: &SeasonStatsRecord) -> Result<(), mongodb::error::Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.collection
+            .replace_one(
+                doc! {
+                    "account_id": &record.account_id,
+                    "shard": &record.shard,
+                    "season_id": &record.season_id,
+                },
+                record,
+                options,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct RankedStatsRepository {
+    collection: Collection<RankedStatsRecord>,
+}
+
+impl RankedStatsRepository {
+    pub fn new(collection: Collection<RankedStatsRecord>) -> Self {
+        RankedStatsRepository { collection }
+    }
+
+    pub async fn find(
+        &self,
+        account_id: &str,
+        shard: &str,
+        season_id: &str,
+    ) -> Result<Option<RankedStatsRecord>, mongodb::error::Error> {
+        self.collection
+            .find_one(
+                doc! { "account_id": account_id, "shard": shard, "season_id": season_id },
+                None,
+            )
+            .await
+    }
+
+    pub async fn save(&self, record: &RankedStatsRecord) -> Result<(), mongodb::error::Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.collection
+            .replace_one(
+                doc! {
+                    "account_id": &record.account_id,
+                    "shard": &record.shard,
+                    "season_id": &record.season_id,
+                },
+                record,
+                options,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+// Stored matches one of the players took part in, within the optional dates, map and game
+// modes
+fn player_matches_filter(
+    account_ids: &[&str],
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    map_name: Option<&str>,
+    game_mode_pattern: Option<String>,
+) -> Document {
+    let mut filter = doc! { "participant_ids": { "$in": account_ids } };
+    let mut created_at = Document::new();
+    if let Some(from) = from {
+        created_at.insert("$gte", from);
+    }
+    if let Some(to) = to {
+        created_at.insert("$lte", to);
+    }
+    if !created_at.is_empty() {
+        filter.insert("created_at", created_at);
+    }
+    if let Some(map_name) = map_name {
+        // Case-insensitive exact match
+        let pattern = format!("^{}$", regex_escape(map_name));
+        filter.insert("map_name", doc! { "$regex": pattern, "$options": "i" });
+    }
+    if let Some(pattern) = game_mode_pattern {
+        filter.insert("game_mode", doc! { "$regex": pattern });
+    }
+    filter
+}
+
+fn regex_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub struct LandingRepository {
+    collection: Collection<LandingRecord>,
+}
+
+impl LandingRepository {
+    pub fn new(collection: Collection<LandingRecord>) -> Self {
+        LandingRepository { collection }
+    }
+
+    pub async fn find_by_ids(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<LandingRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let cursor = self.collection.find(doc! { "_id": { "$in": ids } }, None).await?;
+        cursor.try_collect().await
+    }
+
+    pub async fn save(&self, record: &LandingRecord) -> Result<(), mongodb::error::Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.collection
+            .replace_one(doc! { "_id": &record.id }, record, options)
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct KillRepository {
+    collection: Collection<KillRecord>,
+    recorded: Collection<KillMatchRecord>,
+}
+
+impl KillRepository {
+    pub fn new(collection: Collection<KillRecord>, recorded: Collection<KillMatchRecord>) -> Self {
+        KillRepository {
+            collection,
+            recorded,
+        }
+    }
+
+    /// Returns which of the matches have their kills stored.
+    pub async fn find_recorded_match_ids(
+        &self,
+        match_ids: &[String],
+    ) -> Result<HashSet<String>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if match_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+        let cursor = self
+            .recorded
+            .clone_with_type::<Document>()
+            .find(doc! { "_id": { "$in": match_ids } }, options)
+            .await?;
+        let docs: Vec<Document> = cursor.try_collect().await?;
+
+        Ok(docs
+            .iter()
+            .filter_map(|d| d.get_str("_id").ok().map(str::to_string))
+            .collect())
+    }
+
+    /// Kills the player made or suffered, in the given matches or in every match.
+    pub async fn find_for_account(
+        &self,
+        account_id: &str,
+        match_ids: Option<&[String]>,
+    ) -> Result<Vec<KillRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        let mut filter = doc! {
+            "$or": [
+                { "killer.account_id": account_id },
+                { "victim.account_id": account_id },
+            ]
+        };
+        if let Some(match_ids) = match_ids {
+            filter.insert("match_id", doc! { "$in": match_ids });
+        }
+        let cursor = self.collection.find(filter, None).await?;
+        cursor.try_collect().await
+    }
+
+    /// Stores the kills of a match in one write, then marks the match as recorded. Kill ids
+    /// are derived from the telemetry, so the kills a concurrent or failed save of the same
+    /// match already wrote are the same documents and are left as they are.
+    pub async fn save_match(
+        &self,
+        match_id: &str,
+        kills: &[KillRecord],
+    ) -> Result<(), mongodb::error::Error> {
+        if !kills.is_empty() {
+            let options = InsertManyOptions::builder().ordered(false).build();
+            if let Err(e) = self.collection.insert_many(kills, options).await {
+                let only_duplicates = matches!(
+                    *e.kind,
+                    ErrorKind::BulkWrite(BulkWriteFailure {
+                        write_errors: Some(ref errors),
+                        write_concern_error: None,
+                        ..
+                    }) if errors.iter().all(|error| error.code == DUPLICATE_KEY)
+                );
+                if !only_duplicates {
+                    return Err(e);
+                }
+            }
+        }
+
+        let marker = KillMatchRecord {
+            id: match_id.to_string(),
+            kills: kills.len() as u32,
+            recorded_at: Utc::now(),
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.recorded
+            .replace_one(doc! { "_id": match_id }, marker, options)
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct WeaponRepository {
+    collection: Collection<MatchWeaponsRecord>,
+}
+
+impl WeaponRepository {
+    pub fn new(collection: Collection<MatchWeaponsRecord>) -> Self {
+        WeaponRepository { collection }
+    }
+
+    /// Stored weapon totals of the matches, with only the given player's entry.
+    pub async fn find_for_account(
+        &self,
+        match_ids: &[String],
+        account_id: &str,
+    ) -> Result<Vec<MatchWeaponsRecord>, mongodb::error::Error> {
+        use futures::stream::TryStreamExt;
+
+        if match_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let options = FindOptions::builder()
+            .projection(doc! {
+                "shard": 1,
+                "players": { "$elemMatch": { "account_id": account_id } },
+            })
+            .build();
+        let cursor = self
+            .collection
+            .find(doc! { "_id": { "$in": match_ids } }, options)
+            .await?;
+        cursor.try_collect().await
+    }
+
+    pub async fn save(&self, record: &MatchWeaponsRecord) -> Result<(), mongodb::error::Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+
+        self.collection
+            .replace_one(doc! { "_id": &record.id }, record, options)
+            .await?;
+        Ok(())
+    }
 }
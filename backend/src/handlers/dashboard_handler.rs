@@ -4,22 +4,24 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     handlers::player_handler::{AppState, ErrorResponse},
-    models::StatsResponse,
+    models::{ModeFilter, RankedModeStats, StatsPeriod, StatsResponse},
 };
 
 #[derive(Debug, Deserialize)]
 pub struct DashboardQuery {
     pub ids: String, // Comma-separated player IDs
-    #[serde(default = "default_period")]
-    pub period: String,
+    pub period: Option<String>, // "7d", "30d" or "90d", defaults to "7d"
+    pub from: Option<String>,   // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
     #[serde(default = "default_mode")]
     pub mode: String,
+    pub perspective: Option<String>, // "fpp", "tpp" or "all"
     #[serde(default = "default_shard")]
     pub shard: String,
-}
-
-fn default_period() -> String {
-    "7d".to_string()
+    #[serde(default)]
+    pub ranked: bool, // include the current ranked standing of each player
+    #[serde(default)]
+    pub humans_only: bool, // kills and K/D against human players only
 }
 
 fn default_mode() -> String {
@@ -42,9 +44,14 @@ pub struct PlayerStatsData {
     pub player_id: String,
     pub name: String,
     pub stats: StatsResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranked: Option<RankedModeStats>,
 }
 
-// GET /api/dashboard?ids=id1,id2,id3&period=7d&mode=all&shard=steam
+// GET /api/dashboard?ids=id1,id2,id3&period=7d&mode=all&perspective=fpp&shard=steam
+// GET /api/dashboard?ids=id1,id2&from=1704067200&to=1704672000
+// GET /api/dashboard?ids=id1,id2&ranked=true
+// GET /api/dashboard?ids=id1,id2&humans_only=true
 pub async fn get_dashboard_stats(
     State(state): State<AppState>,
     Query(query): Query<DashboardQuery>,
@@ -83,6 +90,17 @@ pub async fn get_dashboard_stats(
         ));
     }
 
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(|e| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))
+    })?;
+
     let mut players_with_stats = Vec::new();
 
     for player_id in player_ids {
@@ -109,7 +127,7 @@ pub async fn get_dashboard_stats(
 
         // Get stats using the shared stats_service from state
         let stats = match state.stats_service
-            .get_or_compute_stats(&player_id, &query.period, &query.mode, &query.shard)
+            .get_or_compute_stats(&player_id, &period, &mode, &query.shard)
             .await
         {
             Ok(s) => s,
@@ -123,16 +141,36 @@ pub async fn get_dashboard_stats(
             }
         };
 
+        // Ranked standings are a bonus, the dashboard is still served without them
+        let ranked = if query.ranked {
+            match state.season_service.get_ranked_stats(&player, "current").await {
+                Ok(record) => record.and_then(|r| r.main_mode()),
+                Err(e) => {
+                    tracing::warn!("Failed to fetch ranked stats of {}: {}", player.name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let stats = if query.humans_only {
+            StatsResponse::from(stats).humans_only()
+        } else {
+            StatsResponse::from(stats)
+        };
+
         players_with_stats.push(PlayerStatsData {
             player_id: player_id.to_hex(),
             name: player.name.clone(),
-            stats: StatsResponse::from(stats),
+            stats,
+            ranked,
         });
     }
 
     Ok(Json(DashboardResponse {
         players: players_with_stats,
-        period: query.period,
-        mode: query.mode,
+        period: period.label(),
+        mode: mode.label(),
     }))
 }
@@ -0,0 +1,138 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use mongodb::bson::oid::ObjectId;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::{LandingHeatmapResponse, MapHeatmap, ModeFilter, Player, StatsPeriod},
+};
+
+const MIN_CELL_SIZE: f64 = 50.0;
+const MAX_CELL_SIZE: f64 = 2000.0;
+
+#[derive(Debug, Deserialize)]
+pub struct LandingsQuery {
+    pub ids: Option<String>,    // Comma-separated player IDs, for /api/landings only
+    pub period: Option<String>, // "7d", "30d" or "90d", defaults to "7d"
+    pub from: Option<String>,   // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>, // "fpp", "tpp" or "all"
+    pub map: Option<String>,         // PUBG map name, e.g. "Baltic_Main"
+    #[serde(default = "default_cell_size")]
+    pub cell_size: f64, // in meters
+}
+
+fn default_mode() -> String {
+    "all".to_string()
+}
+
+fn default_cell_size() -> f64 {
+    500.0
+}
+
+fn bad_request(error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }))
+}
+
+// GET /api/players/:id/landings?period=30d&mode=squad&map=Baltic_Main&cell_size=500
+pub async fn get_player_landings(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<LandingsQuery>,
+) -> Result<Json<LandingHeatmapResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    landing_heatmap(&state, vec![player], &query).await
+}
+
+// GET /api/landings?ids=id1,id2,id3&period=30d&mode=squad&cell_size=250
+pub async fn get_group_landings(
+    State(state): State<AppState>,
+    Query(query): Query<LandingsQuery>,
+) -> Result<Json<LandingHeatmapResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut player_ids: Vec<ObjectId> = query
+        .ids
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|id| !id.trim().is_empty())
+        .map(|id| ObjectId::parse_str(id.trim()))
+        .collect::<Result<_, _>>()
+        .map_err(|_| bad_request("Invalid player ID format".to_string()))?;
+    let mut seen = HashSet::new();
+    player_ids.retain(|id| seen.insert(*id));
+
+    if player_ids.is_empty() {
+        return Err(bad_request("At least one player ID is required".to_string()));
+    }
+
+    if player_ids.len() > 10 {
+        return Err(bad_request("Maximum 10 players can be compared".to_string()));
+    }
+
+    let mut players = Vec::with_capacity(player_ids.len());
+    for player_id in player_ids {
+        players.push(find_player(&state, &player_id.to_hex()).await?);
+    }
+
+    landing_heatmap(&state, players, &query).await
+}
+
+async fn landing_heatmap(
+    state: &AppState,
+    players: Vec<Player>,
+    query: &LandingsQuery,
+) -> Result<Json<LandingHeatmapResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !(MIN_CELL_SIZE..=MAX_CELL_SIZE).contains(&query.cell_size) {
+        return Err(bad_request(format!(
+            "cell_size must be between {} and {} meters",
+            MIN_CELL_SIZE, MAX_CELL_SIZE
+        )));
+    }
+
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(bad_request)?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?;
+
+    let map = query.map.as_deref().filter(|map| !map.is_empty());
+
+    let landings = match state
+        .landing_service
+        .get_landings(&players, &period, &mode, map)
+        .await
+    {
+        Ok(landings) => landings,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to fetch landings: {}", e),
+                }),
+            ))
+        }
+    };
+
+    let names: HashMap<String, String> = players
+        .iter()
+        .map(|p| (p.account_id.clone(), p.name.clone()))
+        .collect();
+
+    Ok(Json(LandingHeatmapResponse {
+        player_ids: players
+            .iter()
+            .filter_map(|p| p.id.map(|id| id.to_hex()))
+            .collect(),
+        period: period.label(),
+        mode: mode.label(),
+        cell_size: query.cell_size,
+        maps: MapHeatmap::build(&landings, &names, query.cell_size),
+    }))
+}
@@ -0,0 +1,273 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::{
+        is_valid_match_id, is_valid_shard, stats::parse_timestamp, MatchDetailResponse,
+        MatchHistoryFilter, MatchHistoryResponse, MatchTimelineResponse, MatchTracksResponse,
+        ModeFilter, PubgMatchResponse, SHARDS,
+    },
+    services::TelemetryError,
+};
+
+const MAX_PAGE_SIZE: u64 = 100;
+const MAX_TRACK_INTERVAL: u32 = 120;
+
+#[derive(Debug, Deserialize)]
+pub struct MatchHistoryQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>,
+    pub map: Option<String>,
+    pub from: Option<String>, // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
+    #[serde(default = "default_order")]
+    pub order: String, // "desc" (most recent first) or "asc"
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    20
+}
+
+fn default_mode() -> String {
+    "all".to_string()
+}
+
+fn default_order() -> String {
+    "desc".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchQuery {
+    #[serde(default = "default_shard")]
+    pub shard: String,
+}
+
+fn default_shard() -> String {
+    "steam".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TracksQuery {
+    #[serde(default = "default_shard")]
+    pub shard: String,
+    #[serde(default = "default_interval")]
+    pub interval: u32, // seconds between two points
+}
+
+fn default_interval() -> u32 {
+    10
+}
+
+fn bad_request(error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }))
+}
+
+// GET /api/players/:id/matches/history?page=1&page_size=20&mode=squad&map=Baltic_Main&from=...&to=...&order=desc
+pub async fn get_player_match_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<MatchHistoryQuery>,
+) -> Result<Json<MatchHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    if query.page == 0 || query.page_size == 0 || query.page_size > MAX_PAGE_SIZE {
+        return Err(bad_request(format!(
+            "page must be at least 1 and page_size between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let oldest_first = match query.order.as_str() {
+        "desc" => false,
+        "asc" => true,
+        other => return Err(bad_request(format!("Invalid order: {} (expected asc or desc)", other))),
+    };
+
+    let filter = MatchHistoryFilter {
+        mode: ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?,
+        map: query.map.filter(|map| !map.is_empty()),
+        from: query.from.as_deref().map(parse_timestamp).transpose().map_err(bad_request)?,
+        to: query.to.as_deref().map(parse_timestamp).transpose().map_err(bad_request)?,
+        oldest_first,
+        page: query.page,
+        page_size: query.page_size,
+    };
+
+    match state.match_service.get_player_history(&player, &filter).await {
+        Ok((total, matches)) => Ok(Json(MatchHistoryResponse {
+            player_id: id,
+            page: filter.page,
+            page_size: filter.page_size,
+            total,
+            matches,
+        })),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch match history: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/matches/:id?shard=steam
+pub async fn get_match(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<MatchQuery>,
+) -> Result<Json<MatchDetailResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let match_data = find_match(&state, &query.shard, &id).await?;
+    let tracked = tracked_players(&state, &match_data).await?;
+
+    Ok(Json(MatchDetailResponse::from_match(&match_data, &tracked)))
+}
+
+// GET /api/matches/:id/timeline?shard=steam
+pub async fn get_match_timeline(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<MatchQuery>,
+) -> Result<Json<MatchTimelineResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let match_data = find_match(&state, &query.shard, &id).await?;
+    let tracked = tracked_players(&state, &match_data).await?;
+
+    let events = match state
+        .telemetry_service
+        .get_timeline(&match_data, tracked)
+        .await
+    {
+        Ok(events) => events,
+        Err(e) => return Err(telemetry_error(e, "Failed to build match timeline")),
+    };
+
+    let attributes = &match_data.data.attributes;
+    Ok(Json(MatchTimelineResponse {
+        match_id: match_data.data.id.clone(),
+        map_name: attributes.map_name.clone(),
+        game_mode: attributes.game_mode.clone(),
+        created_at: attributes.created_at.clone(),
+        events,
+    }))
+}
+
+// GET /api/matches/:id/tracks?shard=steam&interval=10
+pub async fn get_match_tracks(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<TracksQuery>,
+) -> Result<Json<MatchTracksResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if query.interval == 0 || query.interval > MAX_TRACK_INTERVAL {
+        return Err(bad_request(format!(
+            "interval must be between 1 and {} seconds",
+            MAX_TRACK_INTERVAL
+        )));
+    }
+
+    let match_data = find_match(&state, &query.shard, &id).await?;
+    let tracked = tracked_players(&state, &match_data).await?;
+
+    let (players, zones) = match state
+        .telemetry_service
+        .get_tracks(&match_data, tracked, query.interval)
+        .await
+    {
+        Ok(tracks) => tracks,
+        Err(e) => return Err(telemetry_error(e, "Failed to build match tracks")),
+    };
+
+    Ok(Json(MatchTracksResponse {
+        match_id: match_data.data.id.clone(),
+        map_name: match_data.data.attributes.map_name.clone(),
+        interval: query.interval,
+        players,
+        zones,
+    }))
+}
+
+fn telemetry_error(e: TelemetryError, context: &str) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match e {
+        TelemetryError::InvalidMatchId(_) => StatusCode::BAD_REQUEST,
+        TelemetryError::MatchNotFound(_) | TelemetryError::NoTelemetry(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    (
+        status,
+        Json(ErrorResponse {
+            error: format!("{}: {}", context, e),
+        }),
+    )
+}
+
+async fn find_match(
+    state: &AppState,
+    shard: &str,
+    id: &str,
+) -> Result<PubgMatchResponse, (StatusCode, Json<ErrorResponse>)> {
+    // Both end up in the PUBG API URL
+    if !is_valid_shard(shard) {
+        return Err(bad_request(format!(
+            "Invalid shard: {} (expected one of {})",
+            shard,
+            SHARDS.join(", ")
+        )));
+    }
+    if !is_valid_match_id(id) {
+        return Err(bad_request(format!("Invalid match id: {}", id)));
+    }
+
+    match state.match_service.get_match(shard, id).await {
+        Ok(Some(m)) => Ok(m),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Match not found".to_string(),
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch match: {}", e),
+            }),
+        )),
+    }
+}
+
+// Links the participants that are tracked players to their player ids
+async fn tracked_players(
+    state: &AppState,
+    match_data: &PubgMatchResponse,
+) -> Result<HashMap<String, String>, (StatusCode, Json<ErrorResponse>)> {
+    let account_ids: Vec<String> = match_data
+        .participants()
+        .map(|p| p.player_id.clone())
+        .collect();
+
+    match state
+        .player_service
+        .get_players_by_account_ids(&account_ids)
+        .await
+    {
+        Ok(players) => Ok(players
+            .into_iter()
+            .filter_map(|p| p.id.map(|id| (p.account_id, id.to_hex())))
+            .collect()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch players: {}", e),
+            }),
+        )),
+    }
+}
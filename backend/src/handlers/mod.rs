@@ -1,4 +1,10 @@
 pub mod dashboard_handler;
+pub mod landing_handler;
+pub mod match_handler;
 pub mod player_handler;
+pub mod rival_handler;
+pub mod season_handler;
+pub mod synergy_handler;
+pub mod weapon_handler;
 
 pub use player_handler::{AppState, AppStateInner};
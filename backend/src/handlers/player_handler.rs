@@ -5,8 +5,12 @@ use std::sync::Arc;
 use validator::Validate;
 
 use crate::{
-    models::{CreatePlayerRequest, PlayerResponse, StatsResponse},
-    services::{PlayerService, StatsService},
+    models::{CreatePlayerRequest, ModeFilter, Player, PlayerResponse, StatsPeriod, StatsResponse},
+    services::{
+        stats_service::StatsMetricsSnapshot, KillService, LandingService, MatchService,
+        PlayerService, SeasonService, StatsService, SynergyService, TelemetryService,
+        WeaponService,
+    },
 };
 
 pub type AppState = Arc<AppStateInner>;
@@ -15,6 +19,13 @@ pub type AppState = Arc<AppStateInner>;
 pub struct AppStateInner {
     pub player_service: Arc<PlayerService>,
     pub stats_service: Arc<StatsService>,
+    pub match_service: Arc<MatchService>,
+    pub synergy_service: Arc<SynergyService>,
+    pub season_service: Arc<SeasonService>,
+    pub telemetry_service: Arc<TelemetryService>,
+    pub weapon_service: Arc<WeaponService>,
+    pub landing_service: Arc<LandingService>,
+    pub kill_service: Arc<KillService>,
 }
 
 #[derive(Debug, Serialize)]
@@ -22,6 +33,37 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
+/// Loads the player of a path id, answering 400 or 404 when it is invalid or unknown.
+pub(crate) async fn find_player(
+    state: &AppState,
+    id: &str,
+) -> Result<Player, (StatusCode, Json<ErrorResponse>)> {
+    let object_id = ObjectId::parse_str(id).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid player ID format".to_string(),
+            }),
+        )
+    })?;
+
+    match state.player_service.get_player(&object_id).await {
+        Ok(Some(p)) => Ok(p),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Player not found".to_string(),
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch player: {}", e),
+            }),
+        )),
+    }
+}
+
 // POST /api/players
 pub async fn create_player(
     State(state): State<AppState>,
@@ -187,16 +229,16 @@ pub async fn get_player_matches(
 
 #[derive(Debug, Deserialize)]
 pub struct StatsQuery {
-    #[serde(default = "default_period")]
-    pub period: String,
+    pub period: Option<String>, // "7d", "30d" or "90d", defaults to "7d"
+    pub from: Option<String>,   // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
     #[serde(default = "default_mode")]
     pub mode: String,
+    pub perspective: Option<String>, // "fpp", "tpp" or "all"
     #[serde(default = "default_shard")]
     pub shard: String,
-}
-
-fn default_period() -> String {
-    "7d".to_string()
+    #[serde(default)]
+    pub humans_only: bool, // kills and K/D against human players only
 }
 
 fn default_mode() -> String {
@@ -207,7 +249,9 @@ fn default_shard() -> String {
     "steam".to_string()
 }
 
-// GET /api/players/:id/stats?period=7d&mode=all&shard=steam
+// GET /api/players/:id/stats?period=7d&mode=all&perspective=fpp&shard=steam
+// GET /api/players/:id/stats?from=2024-01-01T00:00:00Z&to=2024-01-08T00:00:00Z
+// GET /api/players/:id/stats?period=30d&humans_only=true
 pub async fn get_player_stats(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -222,6 +266,17 @@ pub async fn get_player_stats(
         )
     })?;
 
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(|e| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e }))
+    })?;
+
     // Get player to verify it exists
     let _player = match state.player_service.get_player(&object_id).await {
         Ok(Some(p)) => p,
@@ -246,10 +301,17 @@ pub async fn get_player_stats(
     // Get or compute stats
     match state
         .stats_service
-        .get_or_compute_stats(&object_id, &query.period, &query.mode, &query.shard)
+        .get_or_compute_stats(&object_id, &period, &mode, &query.shard)
         .await
     {
-        Ok(stats) => Ok(Json(StatsResponse::from(stats))),
+        Ok(stats) => {
+            let response = StatsResponse::from(stats);
+            if query.humans_only {
+                Ok(Json(response.humans_only()))
+            } else {
+                Ok(Json(response))
+            }
+        }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -297,28 +359,43 @@ pub async fn refresh_all_players(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ClearCacheQuery {
+    pub player_id: Option<String>, // only this player's stats
+    pub period: Option<String>,    // only this period label, e.g. "7d" or "range:<from>:<to>"
+}
+
 // POST /api/stats/clear-cache
+// POST /api/stats/clear-cache?player_id=...&period=30d
 pub async fn clear_all_stats_cache(
     State(state): State<AppState>,
+    Query(query): Query<ClearCacheQuery>,
 ) -> Result<Json<RefreshAllResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Direct MongoDB deletion to avoid deserialization issues
-    use mongodb::bson::doc;
-    
-    match state.stats_service.db.stats().delete_many(doc! {}, None).await {
-        Ok(result) => {
-            tracing::info!("Deleted {} stats from MongoDB", result.deleted_count);
-            
-            // Also clear memory cache by invalidating all possible combinations
-            // This is a brute force approach but ensures everything is cleared
-            state.stats_service.cache.invalidate_all();
-            
-            Ok(Json(RefreshAllResponse {
-                total: result.deleted_count as usize,
-                success: result.deleted_count as usize,
-                failed: 0,
-                errors: None,
-            }))
-        }
+    let player_id = query
+        .player_id
+        .as_deref()
+        .map(ObjectId::parse_str)
+        .transpose()
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Invalid player ID format".to_string(),
+                }),
+            )
+        })?;
+
+    match state
+        .stats_service
+        .clear(player_id.as_ref(), query.period.as_deref())
+        .await
+    {
+        Ok(deleted) => Ok(Json(RefreshAllResponse {
+            total: deleted as usize,
+            success: deleted as usize,
+            failed: 0,
+            errors: None,
+        })),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -328,6 +405,11 @@ pub async fn clear_all_stats_cache(
     }
 }
 
+// GET /api/stats/metrics
+pub async fn get_stats_metrics(State(state): State<AppState>) -> Json<StatsMetricsSnapshot> {
+    Json(state.stats_service.metrics.snapshot())
+}
+
 #[derive(Debug, Serialize)]
 pub struct RefreshAllResponse {
     pub total: usize,
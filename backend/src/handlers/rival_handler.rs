@@ -0,0 +1,53 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use serde::Deserialize;
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::RivalsResponse,
+};
+
+const MAX_RIVALS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct RivalsQuery {
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+// GET /api/players/:id/rivals?limit=10
+pub async fn get_rivals(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<RivalsQuery>,
+) -> Result<Json<RivalsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if query.limit == 0 || query.limit > MAX_RIVALS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("limit must be between 1 and {}", MAX_RIVALS),
+            }),
+        ));
+    }
+
+    let player = find_player(&state, &id).await?;
+
+    match state.kill_service.get_rivals(&player, query.limit).await {
+        Ok((matches_analyzed, rivals)) => Ok(Json(RivalsResponse {
+            player_id: id,
+            matches_analyzed,
+            nemeses: rivals.nemeses,
+            victims: rivals.victims,
+            head_to_head: rivals.head_to_head,
+        })),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to compute rivals: {}", e),
+            }),
+        )),
+    }
+}
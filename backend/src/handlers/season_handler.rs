@@ -0,0 +1,130 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use serde::Deserialize;
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::{RankedStanding, RankedStandingsResponse, RankedStatsResponse, SeasonStatsResponse},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RankedQuery {
+    pub ids: Option<String>, // Comma-separated player IDs, every tracked player when omitted
+}
+
+// GET /api/players/:id/seasons/:season (season id or "current")
+pub async fn get_player_season_stats(
+    State(state): State<AppState>,
+    Path((id, season)): Path<(String, String)>,
+) -> Result<Json<SeasonStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    match state.season_service.get_season_stats(&player, &season).await {
+        Ok(Some(record)) => Ok(Json(SeasonStatsResponse::new(id, &record))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Season {} not found on shard {}", season, player.shard),
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch season stats: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/players/:id/lifetime
+pub async fn get_player_lifetime_stats(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SeasonStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    match state.season_service.get_lifetime_stats(&player).await {
+        Ok(record) => Ok(Json(SeasonStatsResponse::new(id, &record))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch lifetime stats: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/players/:id/seasons/:season/ranked (season id or "current")
+pub async fn get_player_ranked_stats(
+    State(state): State<AppState>,
+    Path((id, season)): Path<(String, String)>,
+) -> Result<Json<RankedStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    match state.season_service.get_ranked_stats(&player, &season).await {
+        Ok(Some(record)) => Ok(Json(RankedStatsResponse::new(id, &record))),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Season {} not found on shard {}", season, player.shard),
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch ranked stats: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/ranked?ids=id1,id2,id3
+pub async fn get_ranked_standings(
+    State(state): State<AppState>,
+    Query(query): Query<RankedQuery>,
+) -> Result<Json<RankedStandingsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let players = match query.ids.as_deref().filter(|ids| !ids.is_empty()) {
+        Some(ids) => {
+            let mut players = Vec::new();
+            for id in ids.split(',') {
+                let id = id.trim();
+                let player = find_player(&state, id).await?;
+                players.push(player);
+            }
+            players
+        }
+        None => state.player_service.get_all_players().await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to fetch players: {}", e),
+                }),
+            )
+        })?,
+    };
+
+    let mut standings = Vec::with_capacity(players.len());
+    for player in players {
+        // A player whose stats can't be fetched is listed unranked, the others are still served
+        let result = state.season_service.get_ranked_stats(&player, "current").await;
+        let (record, error) = match result {
+            Ok(record) => (record, None),
+            Err(e) => {
+                tracing::warn!("Failed to fetch ranked stats of {}: {}", player.name, e);
+                (None, Some(format!("Failed to fetch ranked stats: {}", e)))
+            }
+        };
+
+        standings.push(RankedStanding {
+            player_id: player.id.map(|id| id.to_hex()).unwrap_or_default(),
+            name: player.name,
+            season_id: record.as_ref().map(|r| r.season_id.clone()),
+            ranked: record.and_then(|r| r.main_mode()),
+            error,
+        });
+    }
+
+    // Unranked players last
+    standings.sort_by_key(|s| std::cmp::Reverse(s.ranked.as_ref().map(|r| r.rank_points)));
+
+    Ok(Json(RankedStandingsResponse { players: standings }))
+}
@@ -0,0 +1,232 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use mongodb::bson::oid::ObjectId;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::{
+        stats::is_bot, ModeFilter, PlayerResponse, StatsPeriod, SynergyResponse,
+        TeammatesResponse,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SynergyQuery {
+    pub ids: String, // Comma-separated player IDs, at least two
+    pub period: Option<String>, // "7d", "30d" or "90d", defaults to "7d"
+    pub from: Option<String>,   // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>, // "fpp", "tpp" or "all"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeammatesQuery {
+    pub period: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const MAX_TEAMMATES: usize = 100;
+
+fn default_mode() -> String {
+    "all".to_string()
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+fn bad_request(error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }))
+}
+
+// GET /api/synergy?ids=id1,id2,id3&period=30d&mode=squad&perspective=fpp
+pub async fn get_synergy(
+    State(state): State<AppState>,
+    Query(query): Query<SynergyQuery>,
+) -> Result<Json<SynergyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut player_ids: Vec<ObjectId> = query
+        .ids
+        .split(',')
+        .map(|id| ObjectId::parse_str(id.trim()))
+        .collect::<Result<_, _>>()
+        .map_err(|_| bad_request("Invalid player ID format".to_string()))?;
+    let mut seen = HashSet::new();
+    player_ids.retain(|id| seen.insert(*id));
+
+    if player_ids.len() < 2 {
+        return Err(bad_request("At least two player IDs are required".to_string()));
+    }
+
+    if player_ids.len() > 10 {
+        return Err(bad_request("Maximum 10 players can be compared".to_string()));
+    }
+
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(bad_request)?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?;
+
+    let mut players = Vec::with_capacity(player_ids.len());
+    for player_id in player_ids {
+        match state.player_service.get_player(&player_id).await {
+            Ok(Some(p)) => players.push(p),
+            Ok(None) => {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: format!("Player {} not found", player_id.to_hex()),
+                    }),
+                ))
+            }
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: format!("Failed to fetch player: {}", e),
+                    }),
+                ))
+            }
+        }
+    }
+
+    match state.synergy_service.get_synergy(&players, &period, &mode).await {
+        Ok(stats) => Ok(Json(SynergyResponse {
+            period: period.label(),
+            mode: mode.label(),
+            stats,
+        })),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to compute synergy: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/players/:id/teammates?period=30d&mode=squad&limit=20
+pub async fn get_teammates(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<TeammatesQuery>,
+) -> Result<Json<TeammatesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if query.limit == 0 || query.limit > MAX_TEAMMATES {
+        return Err(bad_request(format!("limit must be between 1 and {}", MAX_TEAMMATES)));
+    }
+
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(bad_request)?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?;
+
+    let player = find_player(&state, &id).await?;
+
+    match state
+        .synergy_service
+        .get_teammates(&player, &period, &mode, query.limit)
+        .await
+    {
+        Ok(teammates) => Ok(Json(TeammatesResponse {
+            player_id: id,
+            period: period.label(),
+            mode: mode.label(),
+            teammates,
+        })),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to fetch teammates: {}", e),
+            }),
+        )),
+    }
+}
+
+// POST /api/players/:id/teammates/:account_id/track
+pub async fn track_teammate(
+    State(state): State<AppState>,
+    Path((id, account_id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<PlayerResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let player = find_player(&state, &id).await?;
+
+    if is_bot(&account_id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("{} is a bot and cannot be tracked", account_id),
+            }),
+        ));
+    }
+
+    // Already tracked, nothing to do
+    match state
+        .player_service
+        .get_players_by_account_ids(std::slice::from_ref(&account_id))
+        .await
+    {
+        Ok(mut existing) if !existing.is_empty() => {
+            return Ok((StatusCode::OK, Json(PlayerResponse::from(existing.remove(0)))))
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to fetch players: {}", e),
+                }),
+            ))
+        }
+    }
+
+    // Only players met on the player's roster in a stored match can be tracked from here
+    match state.synergy_service.played_together(&player, &account_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("{} was never on {}'s team", account_id, player.name),
+                }),
+            ))
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: format!("Failed to fetch teammate: {}", e),
+                }),
+            ))
+        }
+    }
+
+    // Looked up by account id, the name may now belong to someone else
+    match state
+        .player_service
+        .add_player_by_account_id(&account_id, &player.shard)
+        .await
+    {
+        Ok(teammate) => Ok((StatusCode::CREATED, Json(PlayerResponse::from(teammate)))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to add player: {}", e),
+            }),
+        )),
+    }
+}
@@ -0,0 +1,141 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use mongodb::bson::oid::ObjectId;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::{
+    handlers::player_handler::{find_player, AppState, ErrorResponse},
+    models::{
+        ModeFilter, StatsPeriod, WeaponComparison, WeaponComparisonResponse, WeaponStatsResponse,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct WeaponsQuery {
+    pub period: Option<String>, // "7d", "30d" or "90d", defaults to "7d"
+    pub from: Option<String>,   // RFC 3339 date or Unix timestamp
+    pub to: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>, // "fpp", "tpp" or "all"
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeaponComparisonQuery {
+    pub ids: String, // Comma-separated player IDs
+    pub period: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: String,
+    pub perspective: Option<String>,
+}
+
+fn default_mode() -> String {
+    "all".to_string()
+}
+
+fn bad_request(error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }))
+}
+
+// GET /api/players/:id/weapons?period=30d&mode=squad&perspective=fpp
+pub async fn get_player_weapons(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<WeaponsQuery>,
+) -> Result<Json<WeaponStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(bad_request)?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?;
+
+    let player = find_player(&state, &id).await?;
+
+    match state
+        .weapon_service
+        .get_player_weapons(&player, &period, &mode)
+        .await
+    {
+        Ok((matches_analyzed, weapons)) => Ok(Json(WeaponStatsResponse {
+            player_id: id,
+            period: period.label(),
+            mode: mode.label(),
+            matches_analyzed,
+            weapons,
+        })),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to compute weapon stats: {}", e),
+            }),
+        )),
+    }
+}
+
+// GET /api/dashboard/weapons?ids=id1,id2,id3&period=7d&mode=all
+pub async fn get_weapon_comparison(
+    State(state): State<AppState>,
+    Query(query): Query<WeaponComparisonQuery>,
+) -> Result<Json<WeaponComparisonResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut player_ids: Vec<ObjectId> = query
+        .ids
+        .split(',')
+        .map(|id| ObjectId::parse_str(id.trim()))
+        .collect::<Result<_, _>>()
+        .map_err(|_| bad_request("Invalid player ID format".to_string()))?;
+    let mut seen = HashSet::new();
+    player_ids.retain(|id| seen.insert(*id));
+
+    if player_ids.is_empty() {
+        return Err(bad_request(
+            "At least one player ID is required".to_string(),
+        ));
+    }
+
+    if player_ids.len() > 10 {
+        return Err(bad_request(
+            "Maximum 10 players can be compared".to_string(),
+        ));
+    }
+
+    let period = StatsPeriod::parse(
+        query.period.as_deref(),
+        query.from.as_deref(),
+        query.to.as_deref(),
+    )
+    .map_err(bad_request)?;
+
+    let mode = ModeFilter::parse(&query.mode, query.perspective.as_deref()).map_err(bad_request)?;
+
+    let mut players = Vec::with_capacity(player_ids.len());
+    for player_id in player_ids {
+        let player = find_player(&state, &player_id.to_hex()).await?;
+
+        match state
+            .weapon_service
+            .get_player_weapons(&player, &period, &mode)
+            .await
+        {
+            Ok((_, weapons)) => players.push((player_id.to_hex(), player.name, weapons)),
+            Err(e) => {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: format!("Failed to compute weapon stats: {}", e),
+                    }),
+                ));
+            }
+        }
+    }
+
+    Ok(Json(WeaponComparisonResponse {
+        period: period.label(),
+        mode: mode.label(),
+        weapons: WeaponComparison::compare(&players),
+    }))
+}
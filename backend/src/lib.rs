@@ -6,3 +6,4 @@ pub mod middleware;
 pub mod models;
 pub mod routes;
 pub mod services;
+pub mod utils;
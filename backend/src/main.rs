@@ -1,22 +1,17 @@
 use axum::{routing::{get, post}, Router, http::StatusCode};
 use std::{net::SocketAddr, sync::Arc};
 
-// Modules
-mod config;
-mod db;
-mod handlers;
-mod middleware;
-mod models;
-mod routes;
-mod services;
-// mod utils;
-
-use config::Config;
-use db::MongoDb;
-use handlers::AppStateInner;
-use middleware::{create_cors_layer, handle_errors, trace_request};
-use routes::create_api_routes;
-use services::{PlayerService, PubgApiService, StatsService};
+use pubg_tracker_api::{
+    config::Config,
+    db::MongoDb,
+    handlers::AppStateInner,
+    middleware::{create_cors_layer, handle_errors, trace_request},
+    routes::create_api_routes,
+    services::{
+        KillService, LandingService, MatchService, PlayerService, PubgApiService, SeasonService,
+        StatsService, SynergyService, TelemetryService, WeaponService,
+    },
+};
 
 #[tokio::main]
 async fn main() {
@@ -56,7 +51,9 @@ async fn main() {
     };
 
     // Create indexes
-    if let Err(e) = mongodb.create_indexes().await {
+    let stats_retention =
+        std::time::Duration::from_secs(config.stats_max_staleness_hours.max(0) as u64 * 3600);
+    if let Err(e) = mongodb.create_indexes(stats_retention).await {
         tracing::error!("Failed to create MongoDB indexes: {}", e);
         std::process::exit(1);
     }
@@ -64,25 +61,91 @@ async fn main() {
     let shared_db = Arc::new(mongodb);
 
     // Initialize services
-    let pubg_api = Arc::new(PubgApiService::new(
-        config.pubg_api_key.clone(),
-        config.pubg_api_base_url.clone(),
+    let pubg_api = Arc::new(
+        PubgApiService::with_rate_limit(
+            config.pubg_api_key.clone(),
+            config.pubg_api_base_url.clone(),
+            config.pubg_api_rate_limit,
+        )
+        .with_rate_limited_matches(config.pubg_api_rate_limit_matches),
+    );
+
+    let match_service = Arc::new(
+        MatchService::new(shared_db.clone(), pubg_api.clone())
+            .with_concurrency(config.match_fetch_concurrency),
+    );
+
+    let synergy_service = Arc::new(SynergyService::new(shared_db.clone(), match_service.clone()));
+
+    let season_service = Arc::new(SeasonService::new(shared_db.clone(), pubg_api.clone()));
+
+    let telemetry_service = Arc::new(TelemetryService::new(
+        config.telemetry_dir.clone(),
+        pubg_api.clone(),
+        match_service.clone(),
     ));
 
-    let stats_service = Arc::new(StatsService::new(shared_db.clone(), pubg_api.clone()));
+    // Telemetry files are around 20MB each, the expired ones are deleted every hour
+    let telemetry_retention =
+        std::time::Duration::from_secs(config.telemetry_retention_hours * 3600);
+    let telemetry_cleanup = telemetry_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match telemetry_cleanup.remove_expired(telemetry_retention).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Deleted {} expired telemetry files", removed),
+                Err(e) => tracing::warn!("Failed to delete expired telemetry files: {}", e),
+            }
+        }
+    });
 
-    let player_service = Arc::new(PlayerService::new(
+    let weapon_service = Arc::new(WeaponService::new(
         shared_db.clone(),
-        pubg_api.clone(),
-        stats_service.clone(),
+        telemetry_service.clone(),
     ));
 
+    let landing_service = Arc::new(LandingService::new(
+        shared_db.clone(),
+        match_service.clone(),
+        telemetry_service.clone(),
+    ));
+
+    let kill_service = Arc::new(KillService::new(
+        shared_db.clone(),
+        telemetry_service.clone(),
+    ));
+
+    let stats_service = Arc::new(
+        StatsService::new(shared_db.clone(), match_service.clone())
+            .with_kill_service(kill_service.clone())
+            .with_max_staleness(chrono::Duration::hours(config.stats_max_staleness_hours)),
+    );
+
+    let player_service = Arc::new(
+        PlayerService::new(
+            shared_db.clone(),
+            pubg_api.clone(),
+            match_service.clone(),
+            stats_service.clone(),
+        )
+        .with_kill_service(kill_service.clone()),
+    );
+
     tracing::info!("All services initialized successfully");
 
     // Create application state
     let app_state = Arc::new(AppStateInner { 
         player_service,
         stats_service: stats_service.clone(),
+        match_service: match_service.clone(),
+        synergy_service,
+        season_service,
+        telemetry_service,
+        weapon_service,
+        landing_service,
+        kill_service,
     });
 
     // Build API routes
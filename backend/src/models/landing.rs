@@ -0,0 +1,202 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::models::{telemetry::TelemetryLocation, PubgMatchResponse};
+
+// Where a tracked player landed in a match, as stored in the `landings` collection. Matches
+// never change, so each landing is read from the telemetry a single time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandingRecord {
+    #[serde(rename = "_id")]
+    pub id: String, // "<match id>:<account id>"
+    pub match_id: String,
+    pub account_id: String,
+    pub shard: String,
+    pub map_name: String,
+    pub game_mode: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    // In meters from the north-west corner of the map, absent when the player did not jump
+    pub location: Option<LandingLocation>,
+    pub placement: i32,
+    pub time_survived: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LandingLocation {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl LandingRecord {
+    /// Builds the record of a participant of the match, `None` if they did not play it.
+    /// `location` is the telemetry position of the landing, in centimeters.
+    pub fn new(
+        match_data: &PubgMatchResponse,
+        account_id: &str,
+        location: Option<&TelemetryLocation>,
+    ) -> Option<Self> {
+        let stats = match_data.participant_stats(account_id)?;
+        let attributes = &match_data.data.attributes;
+        let created_at = DateTime::parse_from_rfc3339(&attributes.created_at)
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Some(LandingRecord {
+            id: LandingRecord::key(&match_data.data.id, account_id),
+            match_id: match_data.data.id.clone(),
+            account_id: account_id.to_string(),
+            shard: attributes.shard_id.clone(),
+            map_name: attributes.map_name.clone(),
+            game_mode: attributes.game_mode.clone(),
+            created_at,
+            location: location.map(|l| LandingLocation {
+                x: l.x / 100.0,
+                y: l.y / 100.0,
+            }),
+            placement: stats.win_place,
+            time_survived: stats.time_survived,
+        })
+    }
+
+    pub fn key(match_id: &str, account_id: &str) -> String {
+        format!("{}:{}", match_id, account_id)
+    }
+}
+
+/// Side of a map in meters, `None` for maps we don't know.
+pub fn map_size(map_name: &str) -> Option<f64> {
+    match map_name {
+        "Baltic_Main" | "Erangel_Main" | "Desert_Main" | "DihorOtok_Main" | "Tiger_Main"
+        | "Kiki_Main" | "Neon_Main" => Some(8160.0),
+        "Savage_Main" => Some(4080.0),
+        "Chimera_Main" => Some(3060.0),
+        "Summerland_Main" => Some(2040.0),
+        "Heaven_Main" => Some(1020.0),
+        _ => None,
+    }
+}
+
+/// One cell of a landing heatmap with the outcome of the games that started there.
+#[derive(Debug, Clone, Serialize)]
+pub struct LandingCell {
+    pub column: u32,
+    pub row: u32,
+    pub x: f64, // north-west corner of the cell, in meters
+    pub y: f64,
+    pub landings: u32,
+    pub matches: u32,
+    pub wins: u32,
+    pub avg_placement: f64,
+    pub avg_time_survived: f64,
+    pub players: Vec<String>, // names of the tracked players who landed there
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MapHeatmap {
+    pub map_name: String,
+    pub map_size: f64, // in meters
+    pub columns: u32,  // the grid is columns x columns cells
+    pub landings: u32,
+    pub cells: Vec<LandingCell>, // only the cells with landings, most landings first
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LandingHeatmapResponse {
+    pub player_ids: Vec<String>,
+    pub period: String,
+    pub mode: String,
+    pub cell_size: f64, // in meters
+    pub maps: Vec<MapHeatmap>,
+}
+
+#[derive(Default)]
+struct CellTally<'a> {
+    landings: u32,
+    matches: BTreeSet<&'a str>,
+    wins: u32,
+    placement: i64,
+    time_survived: f64,
+    players: BTreeSet<&'a str>,
+}
+
+impl MapHeatmap {
+    /// Bins the landings into one grid per map, most played map first. `names` maps account
+    /// ids to player names. Landings outside of the map or on unknown maps are ignored.
+    pub fn build(
+        records: &[LandingRecord],
+        names: &HashMap<String, String>,
+        cell_size: f64,
+    ) -> Vec<MapHeatmap> {
+        let mut by_map: BTreeMap<&str, BTreeMap<(u32, u32), CellTally>> = BTreeMap::new();
+
+        for record in records {
+            let (Some(location), Some(size)) = (record.location, map_size(&record.map_name)) else {
+                continue;
+            };
+            if !(0.0..size).contains(&location.x) || !(0.0..size).contains(&location.y) {
+                continue;
+            }
+
+            let cell = (
+                (location.x / cell_size) as u32,
+                (location.y / cell_size) as u32,
+            );
+            let tally = by_map
+                .entry(record.map_name.as_str())
+                .or_default()
+                .entry(cell)
+                .or_default();
+            tally.landings += 1;
+            tally.matches.insert(&record.match_id);
+            if record.placement == 1 {
+                tally.wins += 1;
+            }
+            tally.placement += record.placement as i64;
+            tally.time_survived += record.time_survived;
+            tally
+                .players
+                .insert(names.get(&record.account_id).unwrap_or(&record.account_id));
+        }
+
+        let mut maps: Vec<MapHeatmap> = by_map
+            .into_iter()
+            .map(|(map_name, cells)| {
+                let map_size = map_size(map_name).unwrap_or_default();
+
+                let mut cells: Vec<LandingCell> = cells
+                    .into_iter()
+                    .map(|((column, row), tally)| LandingCell {
+                        column,
+                        row,
+                        x: column as f64 * cell_size,
+                        y: row as f64 * cell_size,
+                        landings: tally.landings,
+                        matches: tally.matches.len() as u32,
+                        wins: tally.wins,
+                        avg_placement: tally.placement as f64 / tally.landings as f64,
+                        avg_time_survived: tally.time_survived / tally.landings as f64,
+                        players: tally.players.into_iter().map(String::from).collect(),
+                    })
+                    .collect();
+                cells.sort_by(|a, b| {
+                    b.landings
+                        .cmp(&a.landings)
+                        .then_with(|| a.avg_placement.total_cmp(&b.avg_placement))
+                });
+
+                MapHeatmap {
+                    map_name: map_name.to_string(),
+                    map_size,
+                    columns: (map_size / cell_size).ceil() as u32,
+                    landings: cells.iter().map(|c| c.landings).sum(),
+                    cells,
+                }
+            })
+            .collect();
+        maps.sort_by_key(|map| std::cmp::Reverse(map.landings));
+        maps
+    }
+}
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, serde_helpers::chrono_datetime_as_bson_datetime, Document};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::models::{
+    stats::{is_bot, LobbyPerformance},
+    ModeFilter, PubgMatchIncluded, PubgMatchResponse, PubgParticipantStats,
+};
+
+// A PUBG match as stored in the `matches` collection. Matches never change once played, so
+// each one is fetched from the API a single time and shared by every tracked player in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchRecord {
+    #[serde(rename = "_id")]
+    pub id: String, // PUBG match id
+    pub shard: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    pub game_mode: String,
+    pub map_name: String,
+    pub participant_ids: Vec<String>, // account ids of every participant
+    // Raw API document, kept whole so fields we don't model yet are not lost
+    #[serde(default)]
+    pub data: Document,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl MatchRecord {
+    pub fn new(
+        shard: &str,
+        raw: &serde_json::Value,
+        parsed: &PubgMatchResponse,
+    ) -> Result<Self, bson::ser::Error> {
+        let attributes = &parsed.data.attributes;
+        let created_at = DateTime::parse_from_rfc3339(&attributes.created_at)
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        let participant_ids = parsed
+            .included
+            .iter()
+            .filter_map(|included| match included {
+                PubgMatchIncluded::Participant { attributes, .. } => {
+                    Some(attributes.stats.player_id.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(MatchRecord {
+            id: parsed.data.id.clone(),
+            shard: shard.to_string(),
+            created_at,
+            game_mode: attributes.game_mode.clone(),
+            map_name: attributes.map_name.clone(),
+            participant_ids,
+            data: bson::to_document(raw)?,
+            fetched_at: Utc::now(),
+        })
+    }
+
+    pub fn to_response(&self) -> Result<PubgMatchResponse, bson::de::Error> {
+        bson::from_document(self.data.clone())
+    }
+}
+
+/// Filters and pagination applied to a player's match history.
+#[derive(Debug, Clone)]
+pub struct MatchHistoryFilter {
+    pub mode: ModeFilter,
+    pub map: Option<String>, // PUBG map name, e.g. "Baltic_Main"
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub oldest_first: bool,
+    pub page: u64, // starts at 1
+    pub page_size: u64,
+}
+
+/// One match of a player's history, with the player's own performance.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchHistoryEntry {
+    pub match_id: String,
+    pub created_at: String,
+    pub map_name: String,
+    pub game_mode: String,
+    pub duration: i32, // in seconds
+    pub placement: i32,
+    pub kills: i32,
+    pub assists: i32,
+    pub dbnos: i32,
+    pub headshot_kills: i32,
+    pub damage_dealt: f64,
+    pub time_survived: f64,
+    pub death_type: String,
+    // Estimate from the participant account ids, bots are "ai.*"
+    pub humans_in_lobby: i32,
+    pub bots_in_lobby: i32,
+    pub lobby: LobbyPerformance,
+}
+
+impl MatchHistoryEntry {
+    /// Builds the entry from the player's participant in the match, if they played it.
+    pub fn from_match(match_data: &PubgMatchResponse, account_id: &str) -> Option<Self> {
+        let stats = match_data.participant_stats(account_id)?;
+        let attributes = &match_data.data.attributes;
+        let bots = match_data
+            .participants()
+            .filter(|p| is_bot(&p.player_id))
+            .count() as i32;
+
+        Some(MatchHistoryEntry {
+            match_id: match_data.data.id.clone(),
+            created_at: attributes.created_at.clone(),
+            map_name: attributes.map_name.clone(),
+            game_mode: attributes.game_mode.clone(),
+            duration: attributes.duration,
+            placement: stats.win_place,
+            kills: stats.kills,
+            assists: stats.assists,
+            dbnos: stats.dbnos,
+            headshot_kills: stats.headshot_kills,
+            damage_dealt: stats.damage_dealt,
+            time_survived: stats.time_survived,
+            death_type: stats.death_type.clone(),
+            humans_in_lobby: match_data.participants().count() as i32 - bots,
+            bots_in_lobby: bots,
+            lobby: LobbyPerformance::new(match_data, account_id)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchHistoryResponse {
+    pub player_id: String,
+    pub page: u64,
+    pub page_size: u64,
+    pub total: u64,
+    pub matches: Vec<MatchHistoryEntry>,
+}
+
+/// A single match with its rosters, tracked players highlighted.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchDetailResponse {
+    pub match_id: String,
+    pub shard: String,
+    pub created_at: String,
+    pub map_name: String,
+    pub game_mode: String,
+    pub match_type: Option<String>,
+    pub duration: i32,
+    pub is_custom_match: bool,
+    pub rosters: Vec<RosterDetail>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RosterDetail {
+    pub roster_id: String,
+    pub team_id: i32,
+    pub rank: i32,
+    pub won: bool,
+    pub has_tracked_player: bool,
+    pub participants: Vec<ParticipantDetail>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipantDetail {
+    pub account_id: String,
+    pub name: String,
+    pub player_id: Option<String>, // set when the participant is a tracked player
+    pub tracked: bool,
+    pub kills: i32,
+    pub assists: i32,
+    pub dbnos: i32,
+    pub headshot_kills: i32,
+    pub revives: i32,
+    pub damage_dealt: f64,
+    pub longest_kill: f64,
+    pub time_survived: f64,
+    pub win_place: i32,
+    pub death_type: String,
+}
+
+impl ParticipantDetail {
+    fn new(stats: &PubgParticipantStats, player_id: Option<String>) -> Self {
+        ParticipantDetail {
+            account_id: stats.player_id.clone(),
+            name: stats.name.clone(),
+            tracked: player_id.is_some(),
+            player_id,
+            kills: stats.kills,
+            assists: stats.assists,
+            dbnos: stats.dbnos,
+            headshot_kills: stats.headshot_kills,
+            revives: stats.revives,
+            damage_dealt: stats.damage_dealt,
+            longest_kill: stats.longest_kill,
+            time_survived: stats.time_survived,
+            win_place: stats.win_place,
+            death_type: stats.death_type.clone(),
+        }
+    }
+}
+
+impl MatchDetailResponse {
+    /// `tracked` maps account ids of tracked players to their player id.
+    pub fn from_match(match_data: &PubgMatchResponse, tracked: &HashMap<String, String>) -> Self {
+        let attributes = &match_data.data.attributes;
+
+        let rosters = match_data
+            .teams()
+            .into_iter()
+            .map(|team| {
+                let participants: Vec<ParticipantDetail> = team
+                    .members
+                    .iter()
+                    .map(|member| {
+                        ParticipantDetail::new(member, tracked.get(&member.player_id).cloned())
+                    })
+                    .collect();
+
+                RosterDetail {
+                    roster_id: team.roster_id.to_string(),
+                    team_id: team.team_id,
+                    rank: team.rank,
+                    won: team.won,
+                    has_tracked_player: participants.iter().any(|p| p.tracked),
+                    participants,
+                }
+            })
+            .collect();
+
+        MatchDetailResponse {
+            match_id: match_data.data.id.clone(),
+            shard: attributes.shard_id.clone(),
+            created_at: attributes.created_at.clone(),
+            map_name: attributes.map_name.clone(),
+            game_mode: attributes.game_mode.clone(),
+            match_type: attributes.match_type.clone(),
+            duration: attributes.duration,
+            is_custom_match: attributes.is_custom_match,
+            rosters,
+        }
+    }
+}
@@ -1,8 +1,37 @@
 // Placeholder for models module
+pub mod landing;
+pub mod matches;
 pub mod player;
+pub mod rivals;
 pub mod stats;
+pub mod synergy;
+pub mod telemetry;
+pub mod timeline;
+pub mod tracks;
+pub mod weapons;
 pub mod pubg;
+pub mod pubg_stats;
+pub mod season;
 
+pub use landing::{LandingHeatmapResponse, LandingRecord, MapHeatmap};
+pub use matches::{
+    MatchDetailResponse, MatchHistoryEntry, MatchHistoryFilter, MatchHistoryResponse, MatchRecord,
+};
 pub use player::{CreatePlayerRequest, Player, PlayerResponse, PlayerSummary};
-pub use stats::{PlayerStats, StatsResponse};
+pub use rivals::{KillMatchRecord, KillRecord, Rivals, RivalsResponse};
+pub use stats::{ModeFilter, PlayerStats, StatsPeriod, StatsResponse};
+pub use synergy::{SynergyMember, SynergyResponse, SynergyStats, Teammate, TeammatesResponse};
+pub use telemetry::TelemetryEvent;
+pub use timeline::{MatchTimelineResponse, TimelineEvent, TimelineEventKind};
+pub use tracks::{MatchTracksResponse, PlayerTrack, ZoneTrack};
+pub use weapons::{
+    MatchWeapons, MatchWeaponsRecord, WeaponComparison, WeaponComparisonResponse, WeaponStats,
+    WeaponStatsResponse, WeaponTotals,
+};
 pub use pubg::*;
+pub use pubg_stats::*;
+pub use season::{
+    GameModeStatsResponse, RankedModeStats, RankedStanding, RankedStandingsResponse,
+    RankedStatsRecord, RankedStatsResponse, SeasonStatsRecord, SeasonStatsResponse,
+    LIFETIME_SEASON,
+};
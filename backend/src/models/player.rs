@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use validator::Validate;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,10 @@ pub struct Player {
     pub shard: String,
     #[serde(default)]
     pub last_matches: Vec<String>,
+    // Every match id seen since the player was added, most recent first. PUBG only lists
+    // about 14 days of matches, this keeps the older ones.
+    #[serde(default)]
+    pub match_history: Vec<String>,
     pub last_refreshed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -43,6 +48,7 @@ pub struct PlayerResponse {
     pub name: String,
     pub shard: String,
     pub last_matches: Vec<String>,
+    pub tracked_matches: usize,
     pub last_refreshed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub summary: Option<PlayerSummary>,
@@ -50,12 +56,15 @@ pub struct PlayerResponse {
 
 impl From<Player> for PlayerResponse {
     fn from(player: Player) -> Self {
+        let tracked_matches = player.history().len();
+
         PlayerResponse {
             id: player.id.map(|id| id.to_hex()).unwrap_or_default(),
             account_id: player.account_id,
             name: player.name,
             shard: player.shard,
             last_matches: player.last_matches,
+            tracked_matches,
             last_refreshed_at: player.last_refreshed_at,
             created_at: player.created_at,
             summary: player.summary,
@@ -71,9 +80,37 @@ impl Player {
             name,
             shard,
             last_matches: Vec::new(),
+            match_history: Vec::new(),
             last_refreshed_at: None,
             created_at: Utc::now(),
             summary: None,
         }
     }
+
+    /// Match ids to compute stats from. Players stored before the history existed only have
+    /// `last_matches`.
+    pub fn history(&self) -> &[String] {
+        if self.match_history.is_empty() {
+            &self.last_matches
+        } else {
+            &self.match_history
+        }
+    }
+
+    /// Records the match ids currently returned by the PUBG API, keeping every older id.
+    /// The matches themselves must be stored right away, PUBG only serves them for 14 days.
+    pub fn merge_matches(&mut self, latest: Vec<String>) {
+        let mut history = latest.clone();
+        let known: HashSet<&String> = latest.iter().collect();
+
+        history.extend(
+            self.history()
+                .iter()
+                .filter(|id| !known.contains(id))
+                .cloned(),
+        );
+
+        self.match_history = history;
+        self.last_matches = latest;
+    }
 }
@@ -1,4 +1,17 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Platform shards of the PUBG API.
+pub const SHARDS: &[&str] = &["steam", "kakao", "psn", "xbox", "console", "stadia", "tournament"];
+
+pub fn is_valid_shard(shard: &str) -> bool {
+    SHARDS.contains(&shard)
+}
+
+/// Match ids are UUIDs, anything else could alter the API URL or a file path built from them.
+pub fn is_valid_match_id(match_id: &str) -> bool {
+    !match_id.is_empty() && match_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
 
 // PUBG API Player Response
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +63,94 @@ pub struct PubgMatchResponse {
     pub included: Vec<PubgMatchIncluded>,
 }
 
+impl PubgMatchResponse {
+    /// Stats of the participant with the given account id, if they played this match.
+    pub fn participant_stats(&self, account_id: &str) -> Option<&PubgParticipantStats> {
+        self.included.iter().find_map(|included| match included {
+            PubgMatchIncluded::Participant { attributes, .. }
+                if attributes.stats.player_id == account_id =>
+            {
+                Some(&attributes.stats)
+            }
+            _ => None,
+        })
+    }
+
+    /// URL of the telemetry file of the match.
+    pub fn telemetry_url(&self) -> Option<&str> {
+        self.included.iter().find_map(|included| match included {
+            PubgMatchIncluded::Asset { attributes, .. } => Some(attributes.url.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Stats of every participant of the match.
+    pub fn participants(&self) -> impl Iterator<Item = &PubgParticipantStats> {
+        self.included.iter().filter_map(|included| match included {
+            PubgMatchIncluded::Participant { attributes, .. } => Some(&attributes.stats),
+            _ => None,
+        })
+    }
+
+    /// Teams of the match with their members resolved from the roster relationships,
+    /// best ranked first.
+    pub fn teams(&self) -> Vec<MatchTeam<'_>> {
+        let participants: HashMap<&str, &PubgParticipantStats> = self
+            .included
+            .iter()
+            .filter_map(|included| match included {
+                PubgMatchIncluded::Participant { id, attributes } => {
+                    Some((id.as_str(), &attributes.stats))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut teams: Vec<MatchTeam<'_>> = self
+            .included
+            .iter()
+            .filter_map(|included| match included {
+                PubgMatchIncluded::Roster {
+                    id,
+                    attributes,
+                    relationships,
+                } => Some(MatchTeam {
+                    roster_id: id,
+                    team_id: attributes.stats.team_id,
+                    rank: attributes.stats.rank,
+                    won: attributes.won.as_deref() == Some("true"),
+                    members: relationships
+                        .iter()
+                        .flat_map(|r| r.participants.data.iter())
+                        .filter_map(|p| participants.get(p.id.as_str()).copied())
+                        .collect(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        teams.sort_by_key(|team| team.rank);
+        teams
+    }
+
+    /// The team the given account played in.
+    pub fn team_of(&self, account_id: &str) -> Option<MatchTeam<'_>> {
+        self.teams()
+            .into_iter()
+            .find(|team| team.members.iter().any(|m| m.player_id == account_id))
+    }
+}
+
+/// A roster of a match, borrowed from the match response.
+#[derive(Debug, Clone)]
+pub struct MatchTeam<'a> {
+    pub roster_id: &'a str,
+    pub team_id: i32,
+    pub rank: i32,
+    pub won: bool,
+    pub members: Vec<&'a PubgParticipantStats>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PubgMatchData {
     #[serde(rename = "type")]
@@ -87,11 +188,26 @@ pub enum PubgMatchIncluded {
     Roster {
         id: String,
         attributes: PubgRosterAttributes,
+        relationships: Option<PubgRosterRelationships>,
+    },
+    #[serde(rename = "asset")]
+    Asset {
+        id: String,
+        attributes: PubgAssetAttributes,
     },
     #[serde(other)]
     Other,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgAssetAttributes {
+    pub name: Option<String>, // "telemetry"
+    #[serde(rename = "URL")]
+    pub url: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PubgParticipantAttributes {
     pub stats: PubgParticipantStats,
@@ -159,6 +275,23 @@ pub struct PubgRosterStats {
     pub team_id: i32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgRosterRelationships {
+    pub participants: PubgResourceRefs,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgResourceRefs {
+    pub data: Vec<PubgResourceRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgResourceRef {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub id: String,
+}
+
 // Error Response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PubgErrorResponse {
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// PUBG API Seasons Response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgSeasonsResponse {
+    pub data: Vec<PubgSeason>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgSeason {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub id: String, // e.g. "division.bro.official.pc-2018-30"
+    pub attributes: PubgSeasonAttributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgSeasonAttributes {
+    #[serde(rename = "isCurrentSeason")]
+    pub is_current_season: bool,
+    #[serde(rename = "isOffseason")]
+    pub is_offseason: bool,
+}
+
+// PUBG API Player Season Response, also returned for lifetime stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgPlayerSeasonResponse {
+    pub data: PubgPlayerSeasonData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgPlayerSeasonData {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub attributes: PubgPlayerSeasonAttributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgPlayerSeasonAttributes {
+    // Keyed by game mode: "solo", "solo-fpp", "duo", "duo-fpp", "squad", "squad-fpp"
+    #[serde(rename = "gameModeStats")]
+    pub game_mode_stats: HashMap<String, PubgGameModeStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PubgGameModeStats {
+    pub assists: i32,
+    pub boosts: i32,
+    #[serde(rename = "dBNOs")]
+    pub dbnos: i32,
+    #[serde(rename = "damageDealt")]
+    pub damage_dealt: f64,
+    #[serde(rename = "headshotKills")]
+    pub headshot_kills: i32,
+    pub heals: i32,
+    pub kills: i32,
+    #[serde(rename = "longestKill")]
+    pub longest_kill: f64,
+    #[serde(rename = "longestTimeSurvived")]
+    pub longest_time_survived: f64,
+    pub losses: i32,
+    #[serde(rename = "maxKillStreaks")]
+    pub max_kill_streaks: i32,
+    pub revives: i32,
+    #[serde(rename = "rideDistance")]
+    pub ride_distance: f64,
+    #[serde(rename = "roadKills")]
+    pub road_kills: i32,
+    #[serde(rename = "roundMostKills")]
+    pub round_most_kills: i32,
+    #[serde(rename = "roundsPlayed")]
+    pub rounds_played: i32,
+    pub suicides: i32,
+    #[serde(rename = "swimDistance")]
+    pub swim_distance: f64,
+    #[serde(rename = "teamKills")]
+    pub team_kills: i32,
+    #[serde(rename = "timeSurvived")]
+    pub time_survived: f64,
+    #[serde(rename = "top10s")]
+    pub top10s: i32,
+    #[serde(rename = "vehicleDestroys")]
+    pub vehicle_destroys: i32,
+    #[serde(rename = "walkDistance")]
+    pub walk_distance: f64,
+    #[serde(rename = "weaponsAcquired")]
+    pub weapons_acquired: i32,
+    pub wins: i32,
+}
+
+impl PubgGameModeStats {
+    /// Adds the stats of another game mode, used to build the all-modes totals.
+    pub fn merge(&mut self, other: &PubgGameModeStats) {
+        self.assists += other.assists;
+        self.boosts += other.boosts;
+        self.dbnos += other.dbnos;
+        self.damage_dealt += other.damage_dealt;
+        self.headshot_kills += other.headshot_kills;
+        self.heals += other.heals;
+        self.kills += other.kills;
+        self.longest_kill = self.longest_kill.max(other.longest_kill);
+        self.longest_time_survived = self.longest_time_survived.max(other.longest_time_survived);
+        self.losses += other.losses;
+        self.max_kill_streaks = self.max_kill_streaks.max(other.max_kill_streaks);
+        self.revives += other.revives;
+        self.ride_distance += other.ride_distance;
+        self.road_kills += other.road_kills;
+        self.round_most_kills = self.round_most_kills.max(other.round_most_kills);
+        self.rounds_played += other.rounds_played;
+        self.suicides += other.suicides;
+        self.swim_distance += other.swim_distance;
+        self.team_kills += other.team_kills;
+        self.time_survived += other.time_survived;
+        self.top10s += other.top10s;
+        self.vehicle_destroys += other.vehicle_destroys;
+        self.walk_distance += other.walk_distance;
+        self.weapons_acquired += other.weapons_acquired;
+        self.wins += other.wins;
+    }
+}
+
+// PUBG API Ranked Player Stats Response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgRankedStatsResponse {
+    pub data: PubgRankedStatsData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgRankedStatsData {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub attributes: PubgRankedStatsAttributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubgRankedStatsAttributes {
+    // Keyed by ranked game mode, "squad-fpp" or "squad"
+    #[serde(rename = "rankedGameModeStats", default)]
+    pub ranked_game_mode_stats: HashMap<String, PubgRankedGameModeStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PubgRankedTier {
+    pub tier: String, // "Bronze", "Silver", "Gold", "Platinum", "Diamond", "Master"
+    #[serde(rename = "subTier")]
+    pub sub_tier: String, // "1" to "5", empty for Master
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PubgRankedGameModeStats {
+    #[serde(rename = "currentTier")]
+    pub current_tier: PubgRankedTier,
+    #[serde(rename = "currentRankPoint")]
+    pub current_rank_point: i32,
+    #[serde(rename = "bestTier")]
+    pub best_tier: PubgRankedTier,
+    #[serde(rename = "bestRankPoint")]
+    pub best_rank_point: i32,
+    #[serde(rename = "roundsPlayed")]
+    pub rounds_played: i32,
+    #[serde(rename = "avgRank")]
+    pub avg_rank: f64,
+    #[serde(rename = "top10Ratio")]
+    pub top10_ratio: f64, // between 0 and 1
+    #[serde(rename = "winRatio")]
+    pub win_ratio: f64,
+    pub assists: i32,
+    pub wins: i32,
+    pub kda: f64,
+    pub kills: i32,
+    pub deaths: i32,
+    #[serde(rename = "roundMostKills")]
+    pub round_most_kills: i32,
+    #[serde(rename = "longestKill")]
+    pub longest_kill: f64,
+    #[serde(rename = "headshotKills")]
+    pub headshot_kills: i32,
+    #[serde(rename = "damageDealt")]
+    pub damage_dealt: f64,
+    #[serde(rename = "dBNOs")]
+    pub dbnos: i32,
+    pub revives: i32,
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{
+    telemetry::{LogPlayerKillV2, TelemetryCharacter},
+    MatchRecord,
+};
+
+// A kill read from match telemetry, as stored in the `kills` collection. Every kill of the
+// match is kept, not only the ones of tracked players, so players tracked later get their
+// history too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillRecord {
+    #[serde(rename = "_id")]
+    pub id: String, // "<match id>:<victim account id>:<attack id>"
+    pub match_id: String,
+    pub shard: String,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>, // date of the match
+    pub map_name: String,
+    pub game_mode: String,
+    pub killer: Option<KillParticipant>, // absent for deaths to the zone or a fall
+    pub victim: KillParticipant,
+    pub weapon: String, // e.g. "WeapHK416_C"
+    pub damage_reason: String,
+    pub distance: f64, // in meters
+    pub is_suicide: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillParticipant {
+    pub account_id: String,
+    pub name: String,
+    pub team_id: i32,
+}
+
+// Marks a match whose kills are all stored, written once they are. Matches without a marker
+// are read again from their telemetry, whatever kills a failed save left behind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillMatchRecord {
+    #[serde(rename = "_id")]
+    pub id: String, // PUBG match id
+    pub kills: u32,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl From<&TelemetryCharacter> for KillParticipant {
+    fn from(character: &TelemetryCharacter) -> Self {
+        KillParticipant {
+            account_id: character.account_id.clone(),
+            name: character.name.clone(),
+            team_id: character.team_id,
+        }
+    }
+}
+
+impl KillRecord {
+    pub fn new(header: &MatchRecord, kill: &LogPlayerKillV2) -> Self {
+        let info = &kill.killer_damage_info;
+
+        KillRecord {
+            id: format!("{}:{}:{}", header.id, kill.victim.account_id, kill.attack_id),
+            match_id: header.id.clone(),
+            shard: header.shard.clone(),
+            created_at: header.created_at,
+            map_name: header.map_name.clone(),
+            game_mode: header.game_mode.clone(),
+            killer: kill.killer.as_ref().map(KillParticipant::from),
+            victim: KillParticipant::from(&kill.victim),
+            weapon: info.damage_causer_name.clone(),
+            damage_reason: info.damage_reason.clone(),
+            distance: info.distance / 100.0,
+            is_suicide: kill.is_suicide,
+        }
+    }
+
+    /// Kill of an opponent: not a suicide, a team kill or a death to the environment.
+    pub fn is_fight(&self) -> bool {
+        match &self.killer {
+            Some(killer) => {
+                !self.is_suicide
+                    && killer.account_id != self.victim.account_id
+                    && killer.team_id != self.victim.team_id
+            }
+            None => false,
+        }
+    }
+}
+
+/// An opponent a player keeps running into.
+#[derive(Debug, Clone, Serialize)]
+pub struct Rival {
+    pub account_id: String,
+    pub name: String,
+    pub player_id: Option<String>, // set when the opponent is a tracked player
+    pub tracked: bool,
+    pub kills: u32, // nemeses: times they killed the player, victims: times the player killed them
+    pub last_encounter: String,
+    pub last_weapon: String,
+    pub top_weapon: String, // weapon used the most in these kills
+}
+
+/// Kills between the player and another tracked player who ended up in the same lobby.
+#[derive(Debug, Clone, Serialize)]
+pub struct HeadToHead {
+    pub account_id: String,
+    pub name: String,
+    pub player_id: String,
+    pub kills: u32,  // the player killed them
+    pub deaths: u32, // they killed the player
+    pub last_encounter: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RivalsResponse {
+    pub player_id: String,
+    pub matches_analyzed: usize,
+    pub nemeses: Vec<Rival>, // who kills the player the most
+    pub victims: Vec<Rival>, // who the player kills the most
+    pub head_to_head: Vec<HeadToHead>,
+}
+
+#[derive(Default)]
+struct RivalTally<'a> {
+    name: &'a str,
+    kills: u32,
+    last: Option<&'a KillRecord>,
+    weapons: HashMap<&'a str, u32>,
+}
+
+#[derive(Default)]
+struct HeadToHeadTally<'a> {
+    name: &'a str,
+    kills: u32,
+    deaths: u32,
+    last: Option<DateTime<Utc>>,
+}
+
+/// Rivals of a player from the kills they were part of.
+pub struct Rivals {
+    pub nemeses: Vec<Rival>,
+    pub victims: Vec<Rival>,
+    pub head_to_head: Vec<HeadToHead>,
+}
+
+impl Rivals {
+    /// `tracked` maps account ids of tracked players to their player id. `limit` applies to
+    /// nemeses and victims, every head-to-head is kept.
+    pub fn compute(
+        account_id: &str,
+        kills: &[KillRecord],
+        tracked: &HashMap<String, String>,
+        limit: usize,
+    ) -> Self {
+        let mut nemeses: HashMap<&str, RivalTally> = HashMap::new();
+        let mut victims: HashMap<&str, RivalTally> = HashMap::new();
+        let mut head_to_head: HashMap<&str, HeadToHeadTally> = HashMap::new();
+
+        for kill in kills.iter().filter(|k| k.is_fight()) {
+            let Some(killer) = &kill.killer else { continue };
+
+            let (opponent, tally, killed_by_player) = if killer.account_id == account_id {
+                (&kill.victim, victims.entry(&kill.victim.account_id), true)
+            } else if kill.victim.account_id == account_id {
+                (killer, nemeses.entry(&killer.account_id), false)
+            } else {
+                continue;
+            };
+
+            let tally = tally.or_default();
+            tally.name = &opponent.name;
+            tally.kills += 1;
+            *tally.weapons.entry(&kill.weapon).or_default() += 1;
+            if tally.last.is_none_or(|last| kill.created_at >= last.created_at) {
+                tally.last = Some(kill);
+            }
+
+            if tracked.contains_key(&opponent.account_id) {
+                let h2h = head_to_head.entry(&opponent.account_id).or_default();
+                h2h.name = &opponent.name;
+                if killed_by_player {
+                    h2h.kills += 1;
+                } else {
+                    h2h.deaths += 1;
+                }
+                h2h.last = h2h.last.max(Some(kill.created_at));
+            }
+        }
+
+        let mut head_to_head: Vec<HeadToHead> = head_to_head
+            .into_iter()
+            .map(|(opponent, tally)| HeadToHead {
+                account_id: opponent.to_string(),
+                name: tally.name.to_string(),
+                player_id: tracked[opponent].clone(),
+                kills: tally.kills,
+                deaths: tally.deaths,
+                last_encounter: tally.last.map(format_date).unwrap_or_default(),
+            })
+            .collect();
+        head_to_head.sort_by(|a, b| {
+            (b.kills + b.deaths)
+                .cmp(&(a.kills + a.deaths))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Rivals {
+            nemeses: rivals(nemeses, tracked, limit),
+            victims: rivals(victims, tracked, limit),
+            head_to_head,
+        }
+    }
+}
+
+// Most kills first, then most recent encounter
+fn rivals(
+    tallies: HashMap<&str, RivalTally>,
+    tracked: &HashMap<String, String>,
+    limit: usize,
+) -> Vec<Rival> {
+    let mut rivals: Vec<(DateTime<Utc>, Rival)> = tallies
+        .into_iter()
+        .filter_map(|(opponent, tally)| {
+            let last = tally.last?;
+            let top_weapon = tally
+                .weapons
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(weapon, _)| weapon.to_string())
+                .unwrap_or_default();
+            let player_id = tracked.get(opponent).cloned();
+
+            Some((
+                last.created_at,
+                Rival {
+                    account_id: opponent.to_string(),
+                    name: tally.name.to_string(),
+                    tracked: player_id.is_some(),
+                    player_id,
+                    kills: tally.kills,
+                    last_encounter: format_date(last.created_at),
+                    last_weapon: last.weapon.clone(),
+                    top_weapon,
+                },
+            ))
+        })
+        .collect();
+    rivals.sort_by(|(a_last, a), (b_last, b)| b.kills.cmp(&a.kills).then(b_last.cmp(a_last)));
+    rivals.into_iter().take(limit).map(|(_, rival)| rival).collect()
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
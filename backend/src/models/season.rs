@@ -0,0 +1,264 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{stats::percent, PubgGameModeStats, PubgRankedGameModeStats};
+
+/// Season id under which lifetime stats are stored.
+pub const LIFETIME_SEASON: &str = "lifetime";
+
+// Official PUBG stats of a player for one season (or lifetime), as stored in the
+// `season_stats` collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonStatsRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub account_id: String,
+    pub shard: String,
+    pub season_id: String, // PUBG season id or "lifetime"
+    pub is_current_season: bool,
+    pub game_mode_stats: HashMap<String, PubgGameModeStats>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub fetched_at: DateTime<Utc>,
+    // Past seasons never change and are kept forever
+    pub expires_at: Option<bson::DateTime>,
+}
+
+impl SeasonStatsRecord {
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at.to_chrono() > now)
+    }
+}
+
+/// Official stats of one game mode, with the ratios PUBG does not compute.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameModeStatsResponse {
+    pub mode: String,
+    pub rounds_played: i32,
+    pub wins: i32,
+    pub top10s: i32,
+    pub kills: i32,
+    pub assists: i32,
+    pub dbnos: i32,
+    pub headshot_kills: i32,
+    pub revives: i32,
+    pub damage_dealt: f64,
+    pub longest_kill: f64,
+    pub time_survived: f64, // in seconds
+    pub kd_ratio: f64,      // kills per death, a death being a round not won
+    pub win_rate: f64,
+    pub top10_rate: f64,
+    pub headshot_rate: f64,
+    pub damage_per_match: f64,
+    pub avg_survival_time: f64,
+}
+
+impl GameModeStatsResponse {
+    pub fn new(mode: &str, stats: &PubgGameModeStats) -> Self {
+        let deaths = stats.rounds_played - stats.wins;
+        let per_match = |total: f64| {
+            if stats.rounds_played > 0 {
+                total / stats.rounds_played as f64
+            } else {
+                0.0
+            }
+        };
+
+        GameModeStatsResponse {
+            mode: mode.to_string(),
+            rounds_played: stats.rounds_played,
+            wins: stats.wins,
+            top10s: stats.top10s,
+            kills: stats.kills,
+            assists: stats.assists,
+            dbnos: stats.dbnos,
+            headshot_kills: stats.headshot_kills,
+            revives: stats.revives,
+            damage_dealt: stats.damage_dealt,
+            longest_kill: stats.longest_kill,
+            time_survived: stats.time_survived,
+            kd_ratio: if deaths > 0 {
+                stats.kills as f64 / deaths as f64
+            } else {
+                stats.kills as f64
+            },
+            win_rate: percent(stats.wins, stats.rounds_played),
+            top10_rate: percent(stats.top10s, stats.rounds_played),
+            headshot_rate: percent(stats.headshot_kills, stats.kills),
+            damage_per_match: per_match(stats.damage_dealt),
+            avg_survival_time: per_match(stats.time_survived),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeasonStatsResponse {
+    pub player_id: String,
+    pub shard: String,
+    pub season_id: String,
+    pub is_current_season: bool,
+    pub overall: GameModeStatsResponse, // every game mode combined
+    pub modes: Vec<GameModeStatsResponse>, // game modes played, most played first
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl SeasonStatsResponse {
+    pub fn new(player_id: String, record: &SeasonStatsRecord) -> Self {
+        let mut overall = PubgGameModeStats::default();
+        let mut modes: Vec<GameModeStatsResponse> = record
+            .game_mode_stats
+            .iter()
+            .filter(|(_, stats)| stats.rounds_played > 0)
+            .map(|(mode, stats)| {
+                overall.merge(stats);
+                GameModeStatsResponse::new(mode, stats)
+            })
+            .collect();
+        modes.sort_by(|a, b| {
+            b.rounds_played
+                .cmp(&a.rounds_played)
+                .then_with(|| a.mode.cmp(&b.mode))
+        });
+
+        SeasonStatsResponse {
+            player_id,
+            shard: record.shard.clone(),
+            season_id: record.season_id.clone(),
+            is_current_season: record.is_current_season,
+            overall: GameModeStatsResponse::new("all", &overall),
+            modes,
+            fetched_at: record.fetched_at,
+        }
+    }
+}
+
+// Ranked stats of a player for one season, as stored in the `ranked_stats` collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedStatsRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub account_id: String,
+    pub shard: String,
+    pub season_id: String,
+    pub is_current_season: bool,
+    pub ranked_game_mode_stats: HashMap<String, PubgRankedGameModeStats>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
+    pub fetched_at: DateTime<Utc>,
+    pub expires_at: Option<bson::DateTime>,
+}
+
+impl RankedStatsRecord {
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at.to_chrono() > now)
+    }
+
+    /// Ranked stats of every mode played, most played first.
+    pub fn modes(&self) -> Vec<RankedModeStats> {
+        let mut modes: Vec<RankedModeStats> = self
+            .ranked_game_mode_stats
+            .iter()
+            .filter(|(_, stats)| stats.rounds_played > 0)
+            .map(|(mode, stats)| RankedModeStats::new(mode, stats))
+            .collect();
+        modes.sort_by(|a, b| {
+            b.rounds_played
+                .cmp(&a.rounds_played)
+                .then_with(|| a.mode.cmp(&b.mode))
+        });
+        modes
+    }
+
+    /// Standing in the ranked mode the player plays the most.
+    pub fn main_mode(&self) -> Option<RankedModeStats> {
+        self.modes().into_iter().next()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedModeStats {
+    pub mode: String,
+    pub tier: String,
+    pub sub_tier: String,
+    pub rank_points: i32,
+    pub best_tier: String,
+    pub best_sub_tier: String,
+    pub best_rank_points: i32,
+    pub rounds_played: i32,
+    pub wins: i32,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    pub kda: f64,
+    pub win_rate: f64, // en pourcentage
+    pub top10_rate: f64,
+    pub avg_rank: f64,
+    pub damage_per_match: f64,
+}
+
+impl RankedModeStats {
+    pub fn new(mode: &str, stats: &PubgRankedGameModeStats) -> Self {
+        RankedModeStats {
+            mode: mode.to_string(),
+            tier: stats.current_tier.tier.clone(),
+            sub_tier: stats.current_tier.sub_tier.clone(),
+            rank_points: stats.current_rank_point,
+            best_tier: stats.best_tier.tier.clone(),
+            best_sub_tier: stats.best_tier.sub_tier.clone(),
+            best_rank_points: stats.best_rank_point,
+            rounds_played: stats.rounds_played,
+            wins: stats.wins,
+            kills: stats.kills,
+            deaths: stats.deaths,
+            assists: stats.assists,
+            kda: stats.kda,
+            win_rate: stats.win_ratio * 100.0,
+            top10_rate: stats.top10_ratio * 100.0,
+            avg_rank: stats.avg_rank,
+            damage_per_match: if stats.rounds_played > 0 {
+                stats.damage_dealt / stats.rounds_played as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedStatsResponse {
+    pub player_id: String,
+    pub shard: String,
+    pub season_id: String,
+    pub is_current_season: bool,
+    pub modes: Vec<RankedModeStats>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl RankedStatsResponse {
+    pub fn new(player_id: String, record: &RankedStatsRecord) -> Self {
+        RankedStatsResponse {
+            player_id,
+            shard: record.shard.clone(),
+            season_id: record.season_id.clone(),
+            is_current_season: record.is_current_season,
+            modes: record.modes(),
+            fetched_at: record.fetched_at,
+        }
+    }
+}
+
+/// Current ranked standing of a tracked player, `None` when they have not played ranked.
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedStanding {
+    pub player_id: String,
+    pub name: String,
+    pub season_id: Option<String>,
+    pub ranked: Option<RankedModeStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // why the ranked stats are missing, when they failed to load
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedStandingsResponse {
+    pub players: Vec<RankedStanding>, // highest rank points first
+}
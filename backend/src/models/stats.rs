@@ -1,14 +1,16 @@
 use chrono::{DateTime, Utc};
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, serde_helpers::chrono_datetime_as_bson_datetime};
 use serde::{Deserialize, Serialize};
 
+use crate::models::{PubgMatchResponse, PubgParticipantStats};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerStats {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
     pub player_id: ObjectId,
-    pub period: String,      // "7d", "30d", "90d"
-    pub mode: String,        // "solo", "duo", "squad", "all"
+    pub period: String,      // "7d", "30d", "90d" or "range:<from>:<to>"
+    pub mode: String,        // "solo", "duo", "squad", "all", optionally suffixed "-fpp"/"-tpp"
     pub shard: String,       // "steam", "xbox", "psn"
     pub kills: i32,
     pub deaths: i32,
@@ -18,8 +20,100 @@ pub struct PlayerStats {
     pub survival_time: f64,  // en secondes
     pub top1_count: i32,
     pub matches_played: i32,
+    // Participant totals
+    #[serde(default)]
+    pub assists: i32,
+    #[serde(default)]
+    pub dbnos: i32,
+    #[serde(default)]
+    pub headshot_kills: i32,
+    #[serde(default)]
+    pub revives: i32,
+    #[serde(default)]
+    pub heals: i32,
+    #[serde(default)]
+    pub boosts: i32,
+    #[serde(default)]
+    pub weapons_acquired: i32,
+    #[serde(default)]
+    pub walk_distance: f64, // in meters
+    #[serde(default)]
+    pub ride_distance: f64,
+    #[serde(default)]
+    pub swim_distance: f64,
+    #[serde(default)]
+    pub longest_kill: f64, // best distance over the period
+    #[serde(default)]
+    pub top10_count: i32,
+    // Per-match averages
+    #[serde(default)]
+    pub avg_kills: f64,
+    #[serde(default)]
+    pub avg_assists: f64,
+    #[serde(default)]
+    pub avg_dbnos: f64,
+    #[serde(default)]
+    pub damage_per_match: f64,
+    #[serde(default)]
+    pub avg_survival_time: f64,
+    #[serde(default)]
+    pub avg_heals: f64,
+    #[serde(default)]
+    pub avg_boosts: f64,
+    #[serde(default)]
+    pub avg_distance: f64,
+    #[serde(default)]
+    pub avg_placement: f64,
+    #[serde(default)]
+    pub avg_kill_place: f64,
+    // Rates, in percent
+    #[serde(default)]
+    pub headshot_rate: f64,
+    #[serde(default)]
+    pub top10_rate: f64,
+    // AI bots, told apart from humans by their account id
+    #[serde(default)]
+    pub human_kills: i32,
+    #[serde(default)]
+    pub bot_kills: i32,
+    #[serde(default)]
+    pub deaths_to_bots: i32,
+    #[serde(default)]
+    pub bot_headshot_kills: i32,
+    #[serde(default)]
+    pub human_kd_ratio: f64, // human kills over deaths to humans
+    // The split above only comes from the matches with bots whose kills are recorded
+    #[serde(default)]
+    pub matches_with_bots: i32,
+    #[serde(default)]
+    pub matches_with_telemetry: i32,
+    #[serde(default)]
+    pub avg_humans_in_lobby: f64,
+    #[serde(default)]
+    pub avg_bots_in_lobby: f64,
+    #[serde(default)]
+    pub avg_human_placement: f64, // placement among the teams with at least one human
+    // Compared to the whole lobby of each match, bots included
+    #[serde(default)]
+    pub avg_lobby_kills: f64,
+    #[serde(default)]
+    pub avg_lobby_damage: f64,
+    #[serde(default)]
+    pub avg_lobby_survival_time: f64,
+    #[serde(default)]
+    pub avg_kill_percentile: f64,
+    #[serde(default)]
+    pub avg_damage_percentile: f64,
+    #[serde(default)]
+    pub avg_survival_percentile: f64,
+    // BSON dates, for the TTL index on `expires_at`
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub computed_at: DateTime<Utc>,
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub expires_at: DateTime<Utc>,
+    // Served after `expires_at` while a recomputation runs, never stored
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +137,53 @@ pub struct StatsResponse {
     pub survival_time: f64,
     pub top1_count: i32,
     pub matches_played: i32,
+    // Participant totals
+    pub assists: i32,
+    pub dbnos: i32,
+    pub headshot_kills: i32,
+    pub revives: i32,
+    pub heals: i32,
+    pub boosts: i32,
+    pub weapons_acquired: i32,
+    pub walk_distance: f64,
+    pub ride_distance: f64,
+    pub swim_distance: f64,
+    pub longest_kill: f64,
+    pub top10_count: i32,
+    // Per-match averages
+    pub avg_kills: f64,
+    pub avg_assists: f64,
+    pub avg_dbnos: f64,
+    pub damage_per_match: f64,
+    pub avg_survival_time: f64,
+    pub avg_heals: f64,
+    pub avg_boosts: f64,
+    pub avg_distance: f64,
+    pub avg_placement: f64,
+    pub avg_kill_place: f64,
+    // Rates, in percent
+    pub headshot_rate: f64,
+    pub top10_rate: f64,
+    // AI bots
+    pub human_kills: i32,
+    pub bot_kills: i32,
+    pub deaths_to_bots: i32,
+    pub bot_headshot_kills: i32,
+    pub human_kd_ratio: f64,
+    pub matches_with_bots: i32,
+    pub matches_with_telemetry: i32, // the bot split is partial when lower than matches_with_bots
+    pub avg_humans_in_lobby: f64,
+    pub avg_bots_in_lobby: f64,
+    pub avg_human_placement: f64,
+    // Lobby strength and relative performance, percentiles from 0 to 100
+    pub avg_lobby_kills: f64,
+    pub avg_lobby_damage: f64,
+    pub avg_lobby_survival_time: f64,
+    pub avg_kill_percentile: f64,
+    pub avg_damage_percentile: f64,
+    pub avg_survival_percentile: f64,
+    pub humans_only: bool, // kills, deaths, K/D and headshots only count humans
+    pub stale: bool,       // expired, fresh numbers are being computed
     pub computed_at: DateTime<Utc>,
 }
 
@@ -61,11 +202,70 @@ impl From<PlayerStats> for StatsResponse {
             survival_time: stats.survival_time,
             top1_count: stats.top1_count,
             matches_played: stats.matches_played,
+            assists: stats.assists,
+            dbnos: stats.dbnos,
+            headshot_kills: stats.headshot_kills,
+            revives: stats.revives,
+            heals: stats.heals,
+            boosts: stats.boosts,
+            weapons_acquired: stats.weapons_acquired,
+            walk_distance: stats.walk_distance,
+            ride_distance: stats.ride_distance,
+            swim_distance: stats.swim_distance,
+            longest_kill: stats.longest_kill,
+            top10_count: stats.top10_count,
+            avg_kills: stats.avg_kills,
+            avg_assists: stats.avg_assists,
+            avg_dbnos: stats.avg_dbnos,
+            damage_per_match: stats.damage_per_match,
+            avg_survival_time: stats.avg_survival_time,
+            avg_heals: stats.avg_heals,
+            avg_boosts: stats.avg_boosts,
+            avg_distance: stats.avg_distance,
+            avg_placement: stats.avg_placement,
+            avg_kill_place: stats.avg_kill_place,
+            headshot_rate: stats.headshot_rate,
+            top10_rate: stats.top10_rate,
+            human_kills: stats.human_kills,
+            bot_kills: stats.bot_kills,
+            deaths_to_bots: stats.deaths_to_bots,
+            bot_headshot_kills: stats.bot_headshot_kills,
+            human_kd_ratio: stats.human_kd_ratio,
+            matches_with_bots: stats.matches_with_bots,
+            matches_with_telemetry: stats.matches_with_telemetry,
+            avg_humans_in_lobby: stats.avg_humans_in_lobby,
+            avg_bots_in_lobby: stats.avg_bots_in_lobby,
+            avg_human_placement: stats.avg_human_placement,
+            avg_lobby_kills: stats.avg_lobby_kills,
+            avg_lobby_damage: stats.avg_lobby_damage,
+            avg_lobby_survival_time: stats.avg_lobby_survival_time,
+            avg_kill_percentile: stats.avg_kill_percentile,
+            avg_damage_percentile: stats.avg_damage_percentile,
+            avg_survival_percentile: stats.avg_survival_percentile,
+            humans_only: false,
+            stale: stats.stale,
             computed_at: stats.computed_at,
         }
     }
 }
 
+impl StatsResponse {
+    /// Counts kills, deaths and headshots against humans only, bots left aside. Damage, knocks
+    /// and the other totals are not split and still include bots.
+    pub fn humans_only(mut self) -> Self {
+        self.kills = self.human_kills;
+        self.deaths -= self.deaths_to_bots;
+        self.kd_ratio = self.human_kd_ratio;
+        self.headshot_kills -= self.bot_headshot_kills;
+        self.headshot_rate = percent(self.headshot_kills, self.kills);
+        if self.matches_played > 0 {
+            self.avg_kills = self.human_kills as f64 / self.matches_played as f64;
+        }
+        self.humans_only = true;
+        self
+    }
+}
+
 impl PlayerStats {
     pub fn new(
         player_id: ObjectId,
@@ -89,8 +289,477 @@ impl PlayerStats {
             survival_time: 0.0,
             top1_count: 0,
             matches_played: 0,
+            assists: 0,
+            dbnos: 0,
+            headshot_kills: 0,
+            revives: 0,
+            heals: 0,
+            boosts: 0,
+            weapons_acquired: 0,
+            walk_distance: 0.0,
+            ride_distance: 0.0,
+            swim_distance: 0.0,
+            longest_kill: 0.0,
+            top10_count: 0,
+            avg_kills: 0.0,
+            avg_assists: 0.0,
+            avg_dbnos: 0.0,
+            damage_per_match: 0.0,
+            avg_survival_time: 0.0,
+            avg_heals: 0.0,
+            avg_boosts: 0.0,
+            avg_distance: 0.0,
+            avg_placement: 0.0,
+            avg_kill_place: 0.0,
+            headshot_rate: 0.0,
+            top10_rate: 0.0,
+            human_kills: 0,
+            bot_kills: 0,
+            deaths_to_bots: 0,
+            bot_headshot_kills: 0,
+            human_kd_ratio: 0.0,
+            matches_with_bots: 0,
+            matches_with_telemetry: 0,
+            avg_humans_in_lobby: 0.0,
+            avg_bots_in_lobby: 0.0,
+            avg_human_placement: 0.0,
+            avg_lobby_kills: 0.0,
+            avg_lobby_damage: 0.0,
+            avg_lobby_survival_time: 0.0,
+            avg_kill_percentile: 0.0,
+            avg_damage_percentile: 0.0,
+            avg_survival_percentile: 0.0,
             computed_at: now,
             expires_at: now + chrono::Duration::hours(ttl_hours),
+            stale: false,
+        }
+    }
+
+    /// Adds one match played by the player to the totals.
+    pub fn add_match(&mut self, participant: &PubgParticipantStats) {
+        self.matches_played += 1;
+        self.kills += participant.kills;
+        self.damage_dealt += participant.damage_dealt;
+        self.survival_time += participant.time_survived;
+        self.assists += participant.assists;
+        self.dbnos += participant.dbnos;
+        self.headshot_kills += participant.headshot_kills;
+        self.revives += participant.revives;
+        self.heals += participant.heals;
+        self.boosts += participant.boosts;
+        self.weapons_acquired += participant.weapons_acquired;
+        self.walk_distance += participant.walk_distance;
+        self.ride_distance += participant.ride_distance;
+        self.swim_distance += participant.swim_distance;
+        self.longest_kill = self.longest_kill.max(participant.longest_kill);
+
+        if participant.win_place == 1 {
+            self.top1_count += 1;
+        }
+        if participant.win_place <= 10 {
+            self.top10_count += 1;
+        }
+        if participant.death_type != "alive" {
+            self.deaths += 1;
+        }
+
+        // Running means, no totals are kept for placements
+        let n = self.matches_played as f64;
+        self.avg_placement += (participant.win_place as f64 - self.avg_placement) / n;
+        self.avg_kill_place += (participant.kill_place as f64 - self.avg_kill_place) / n;
+    }
+
+    /// Adds who the player faced in a match already added with `add_match`.
+    pub fn add_lobby(&mut self, participant: &PubgParticipantStats, lobby: &LobbyBreakdown) {
+        let bot_kills = lobby.bot_kills.min(participant.kills);
+        self.bot_kills += bot_kills;
+        self.human_kills += participant.kills - bot_kills;
+        self.bot_headshot_kills += lobby.bot_headshot_kills.min(participant.headshot_kills);
+        if lobby.killed_by_bot {
+            self.deaths_to_bots += 1;
+        }
+        if lobby.bots > 0 {
+            self.matches_with_bots += 1;
+            if lobby.with_telemetry {
+                self.matches_with_telemetry += 1;
+            }
+        }
+
+        let n = self.matches_played.max(1) as f64;
+        self.avg_humans_in_lobby += (lobby.humans as f64 - self.avg_humans_in_lobby) / n;
+        self.avg_bots_in_lobby += (lobby.bots as f64 - self.avg_bots_in_lobby) / n;
+        self.avg_human_placement += (lobby.human_placement as f64 - self.avg_human_placement) / n;
+    }
+
+    /// Adds how the player did next to their lobby in a match already added with `add_match`.
+    pub fn add_performance(&mut self, lobby: &LobbyPerformance) {
+        let n = self.matches_played.max(1) as f64;
+        self.avg_lobby_kills += (lobby.avg_kills - self.avg_lobby_kills) / n;
+        self.avg_lobby_damage += (lobby.avg_damage - self.avg_lobby_damage) / n;
+        self.avg_lobby_survival_time +=
+            (lobby.avg_survival_time - self.avg_lobby_survival_time) / n;
+        self.avg_kill_percentile += (lobby.kill_percentile - self.avg_kill_percentile) / n;
+        self.avg_damage_percentile += (lobby.damage_percentile - self.avg_damage_percentile) / n;
+        self.avg_survival_percentile +=
+            (lobby.survival_percentile - self.avg_survival_percentile) / n;
+    }
+
+    /// Computes ratios and per-match averages from the totals.
+    pub fn compute_rates(&mut self) {
+        self.kd_ratio = kd_ratio(self.kills, self.deaths);
+        self.human_kd_ratio = kd_ratio(self.human_kills, self.deaths - self.deaths_to_bots);
+
+        self.headshot_rate = percent(self.headshot_kills, self.kills);
+
+        if self.matches_played == 0 {
+            return;
+        }
+
+        let n = self.matches_played as f64;
+        self.win_rate = percent(self.top1_count, self.matches_played);
+        self.top10_rate = percent(self.top10_count, self.matches_played);
+        self.avg_kills = self.kills as f64 / n;
+        self.avg_assists = self.assists as f64 / n;
+        self.avg_dbnos = self.dbnos as f64 / n;
+        self.damage_per_match = self.damage_dealt / n;
+        self.avg_survival_time = self.survival_time / n;
+        self.avg_heals = self.heals as f64 / n;
+        self.avg_boosts = self.boosts as f64 / n;
+        self.avg_distance = (self.walk_distance + self.ride_distance + self.swim_distance) / n;
+    }
+}
+
+fn kd_ratio(kills: i32, deaths: i32) -> f64 {
+    if deaths > 0 {
+        kills as f64 / deaths as f64
+    } else {
+        kills as f64
+    }
+}
+
+/// Whether a participant is one of the AI bots PUBG fills casual lobbies with.
+pub fn is_bot(account_id: &str) -> bool {
+    account_id.starts_with("ai.")
+}
+
+/// The player's kills of bots and death to a bot in one match, read from its telemetry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BotEncounters {
+    pub bot_kills: i32,
+    pub bot_headshot_kills: i32,
+    pub killed_by_bot: bool,
+}
+
+/// Who a player faced in one match: humans and bots in the lobby and the player's
+/// encounters with bots.
+#[derive(Debug, Clone, Default)]
+pub struct LobbyBreakdown {
+    pub humans: i32, // the player included
+    pub bots: i32,
+    pub human_placement: i32,
+    pub bot_kills: i32,
+    pub bot_headshot_kills: i32,
+    pub killed_by_bot: bool,
+    pub with_telemetry: bool, // the bot encounters were read, not assumed
+}
+
+impl LobbyBreakdown {
+    /// `encounters` comes from the telemetry and is only needed when the lobby has bots,
+    /// without it every kill is counted as a human one.
+    pub fn new(
+        match_data: &PubgMatchResponse,
+        account_id: &str,
+        encounters: Option<&BotEncounters>,
+    ) -> Self {
+        let (bots, humans): (Vec<_>, Vec<_>) =
+            match_data.participants().partition(|p| is_bot(&p.player_id));
+        let with_telemetry = !bots.is_empty() && encounters.is_some();
+        let encounters = encounters.copied().unwrap_or_default();
+
+        // Teams made of bots only don't count in the placement
+        let rank = match_data
+            .participant_stats(account_id)
+            .map_or(0, |p| p.win_place);
+        let human_teams_ahead = match_data
+            .teams()
+            .iter()
+            .filter(|team| team.rank < rank && team.members.iter().any(|m| !is_bot(&m.player_id)))
+            .count() as i32;
+
+        LobbyBreakdown {
+            humans: humans.len() as i32,
+            bots: bots.len() as i32,
+            human_placement: human_teams_ahead + 1,
+            bot_kills: if bots.is_empty() { 0 } else { encounters.bot_kills },
+            bot_headshot_kills: if bots.is_empty() { 0 } else { encounters.bot_headshot_kills },
+            killed_by_bot: !bots.is_empty() && encounters.killed_by_bot,
+            with_telemetry,
+        }
+    }
+}
+
+/// Lobby averages of one match and where the player stands among the other participants.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LobbyPerformance {
+    pub avg_kills: f64,
+    pub avg_damage: f64,
+    pub avg_survival_time: f64, // in seconds
+    // Share of the other participants the player did better than, ties count half
+    pub kill_percentile: f64,
+    pub damage_percentile: f64,
+    pub survival_percentile: f64,
+}
+
+impl LobbyPerformance {
+    /// `None` if the player did not play the match.
+    pub fn new(match_data: &PubgMatchResponse, account_id: &str) -> Option<Self> {
+        let player = match_data.participant_stats(account_id)?;
+        let lobby: Vec<&PubgParticipantStats> = match_data.participants().collect();
+        let others: Vec<&PubgParticipantStats> = lobby
+            .iter()
+            .copied()
+            .filter(|p| p.player_id != account_id)
+            .collect();
+
+        let average = |value: fn(&PubgParticipantStats) -> f64| {
+            lobby.iter().map(|p| value(p)).sum::<f64>() / lobby.len() as f64
+        };
+        let rank = |value: fn(&PubgParticipantStats) -> f64| {
+            percentile(value(player), others.iter().map(|p| value(p)))
+        };
+
+        Some(LobbyPerformance {
+            avg_kills: average(|p| p.kills as f64),
+            avg_damage: average(|p| p.damage_dealt),
+            avg_survival_time: average(|p| p.time_survived),
+            kill_percentile: rank(|p| p.kills as f64),
+            damage_percentile: rank(|p| p.damage_dealt),
+            survival_percentile: rank(|p| p.time_survived),
+        })
+    }
+}
+
+// 50 when there is nobody to compare with
+fn percentile(value: f64, others: impl Iterator<Item = f64>) -> f64 {
+    let (mut below, mut count) = (0.0, 0);
+    for other in others {
+        count += 1;
+        if other < value {
+            below += 1.0;
+        } else if other == value {
+            below += 0.5;
+        }
+    }
+
+    if count > 0 {
+        below / count as f64 * 100.0
+    } else {
+        50.0
+    }
+}
+
+pub(crate) fn percent(count: i32, total: i32) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Whether the match was played in the given mode and time window.
+pub(crate) fn in_scope(
+    match_data: &PubgMatchResponse,
+    mode: &ModeFilter,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> bool {
+    let attributes = &match_data.data.attributes;
+    if !mode.matches(&attributes.game_mode) {
+        return false;
+    }
+
+    match DateTime::parse_from_rfc3339(&attributes.created_at) {
+        Ok(created_at) => {
+            let created_at = created_at.with_timezone(&Utc);
+            created_at >= period_start && created_at <= period_end
+        }
+        Err(_) => true,
+    }
+}
+
+/// Time window stats are computed over: one of the named periods, relative to now, or an
+/// explicit range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Days(i64),
+    Range {
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>, // open-ended when None
+    },
+}
+
+impl StatsPeriod {
+    /// Builds the period from the `period`, `from` and `to` query values. `from`/`to` accept
+    /// RFC 3339 dates or Unix timestamps and take precedence over the "7d" default.
+    pub fn parse(period: Option<&str>, from: Option<&str>, to: Option<&str>) -> Result<Self, String> {
+        if from.is_none() && to.is_none() {
+            return match period.unwrap_or("7d") {
+                "7d" => Ok(StatsPeriod::Days(7)),
+                "30d" => Ok(StatsPeriod::Days(30)),
+                "90d" => Ok(StatsPeriod::Days(90)),
+                other => Err(format!("Invalid period: {} (expected 7d, 30d or 90d)", other)),
+            };
+        }
+
+        if period.is_some() {
+            return Err("Use either period or from/to, not both".to_string());
+        }
+
+        let from = from
+            .ok_or_else(|| "from is required when to is set".to_string())
+            .and_then(parse_timestamp)?;
+        let to = to.map(parse_timestamp).transpose()?;
+
+        if to.is_some_and(|to| to <= from) {
+            return Err("from must be before to".to_string());
+        }
+
+        Ok(StatsPeriod::Range { from, to })
+    }
+
+    /// Label used in cache keys and stored in `PlayerStats.period`.
+    pub fn label(&self) -> String {
+        match self {
+            StatsPeriod::Days(days) => format!("{}d", days),
+            StatsPeriod::Range { from, to } => format!(
+                "range:{}:{}",
+                from.timestamp(),
+                to.map(|to| to.timestamp().to_string()).unwrap_or_else(|| "now".to_string())
+            ),
         }
     }
+
+    /// Start and end of the window as of `now`.
+    pub fn bounds(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self {
+            StatsPeriod::Days(days) => (now - chrono::Duration::days(*days), now),
+            StatsPeriod::Range { from, to } => (*from, to.unwrap_or(now)),
+        }
+    }
+
+    /// How long computed stats stay valid.
+    pub fn ttl(&self, now: DateTime<Utc>) -> chrono::Duration {
+        match self {
+            StatsPeriod::Days(7) => chrono::Duration::hours(24),
+            StatsPeriod::Days(30) => chrono::Duration::hours(72),
+            StatsPeriod::Days(_) => chrono::Duration::hours(168),
+            // A range that is over only changes if older matches get imported
+            StatsPeriod::Range { to: Some(to), .. } if *to <= now => chrono::Duration::days(7),
+            StatsPeriod::Range { .. } => chrono::Duration::hours(1),
+        }
+    }
+}
+
+/// Parses an RFC 3339 date or a Unix timestamp in seconds.
+pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(seconds) = value.parse::<i64>() {
+        return DateTime::from_timestamp(seconds, 0)
+            .ok_or_else(|| format!("Invalid timestamp: {}", value));
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid date: {} (expected RFC 3339 or Unix timestamp)", value))
+}
+
+const TEAM_MODES: [&str; 3] = ["solo", "duo", "squad"];
+
+/// Game mode filter applied when computing stats.
+///
+/// `team_mode` is one of "solo", "duo" or "squad" (`None` for all modes) and `perspective`
+/// is "fpp" or "tpp" (`None` for both).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeFilter {
+    pub team_mode: Option<String>,
+    pub perspective: Option<String>,
+}
+
+impl ModeFilter {
+    pub fn all() -> Self {
+        ModeFilter {
+            team_mode: None,
+            perspective: None,
+        }
+    }
+
+    /// Parses a `mode` query value ("squad", "squad-fpp", "all-tpp", ...) and an optional
+    /// separate `perspective` value ("fpp", "tpp" or "all").
+    pub fn parse(mode: &str, perspective: Option<&str>) -> Result<Self, String> {
+        let mode = mode.trim().to_lowercase();
+        let (team_mode, mode_perspective) = match mode.rsplit_once('-') {
+            Some((team_mode, suffix)) if suffix == "fpp" || suffix == "tpp" => {
+                (team_mode.to_string(), Some(suffix.to_string()))
+            }
+            _ => (mode, None),
+        };
+
+        let team_mode = match team_mode.as_str() {
+            "all" => None,
+            m if TEAM_MODES.contains(&m) => Some(team_mode),
+            _ => return Err(format!("Invalid mode: {}", team_mode)),
+        };
+
+        let perspective = match perspective.map(|p| p.trim().to_lowercase()).as_deref() {
+            None | Some("") | Some("all") => mode_perspective,
+            Some(p @ ("fpp" | "tpp")) => match mode_perspective {
+                Some(ref mp) if mp != p => {
+                    return Err(format!("Mode {} conflicts with perspective {}", mp, p))
+                }
+                _ => Some(p.to_string()),
+            },
+            Some(p) => return Err(format!("Invalid perspective: {}", p)),
+        };
+
+        Ok(ModeFilter {
+            team_mode,
+            perspective,
+        })
+    }
+
+    /// Canonical label used in cache keys and stored in `PlayerStats.mode`.
+    pub fn label(&self) -> String {
+        let team_mode = self.team_mode.as_deref().unwrap_or("all");
+        match &self.perspective {
+            Some(perspective) => format!("{}-{}", team_mode, perspective),
+            None => team_mode.to_string(),
+        }
+    }
+
+    /// Whether a PUBG `gameMode` ("squad-fpp", "normal-duo", "tdm", ...) passes the filter.
+    pub fn matches(&self, game_mode: &str) -> bool {
+        let game_mode = game_mode.strip_prefix("normal-").unwrap_or(game_mode);
+        let (team_mode, perspective) = match game_mode.strip_suffix("-fpp") {
+            Some(team_mode) => (team_mode, "fpp"),
+            None => (game_mode.strip_suffix("-tpp").unwrap_or(game_mode), "tpp"),
+        };
+
+        let team_mode_ok = self.team_mode.as_deref().is_none_or(|m| m == team_mode);
+        let perspective_ok = self.perspective.as_deref().is_none_or(|p| p == perspective);
+
+        team_mode_ok && perspective_ok
+    }
+
+    /// Regex selecting the same `gameMode` values as `matches`, for database queries. `None`
+    /// when every mode passes.
+    pub fn game_mode_pattern(&self) -> Option<String> {
+        let pattern = match (self.team_mode.as_deref(), self.perspective.as_deref()) {
+            (None, None) => return None,
+            (Some(team_mode), Some("fpp")) => format!("^(normal-)?{}-fpp$", team_mode),
+            (Some(team_mode), Some(_)) => format!("^(normal-)?{}(-tpp)?$", team_mode),
+            (Some(team_mode), None) => format!("^(normal-)?{}(-fpp|-tpp)?$", team_mode),
+            (None, Some("fpp")) => "-fpp$".to_string(),
+            (None, Some(_)) => "^(?!.*-fpp$)".to_string(),
+        };
+        Some(pattern)
+    }
 }
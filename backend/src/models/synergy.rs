@@ -0,0 +1,309 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    stats::{in_scope, is_bot, percent},
+    ModeFilter, PubgMatchResponse, StatsPeriod,
+};
+
+/// A tracked player taking part in a synergy comparison.
+#[derive(Debug, Clone)]
+pub struct SynergyMember {
+    pub player_id: String,
+    pub name: String,
+    pub account_id: String,
+}
+
+/// How a group of tracked players performs when they share a roster, compared with their
+/// matches apart. A match counts as "together" when at least two of them are on the same team.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SynergyStats {
+    pub matches_together: i32,
+    pub wins_together: i32,
+    pub win_rate_together: f64,
+    pub avg_team_placement: f64,
+    pub combined_kills: i32, // kills of the group members of the roster
+    pub avg_combined_kills: f64,
+    pub combined_damage: f64,
+    pub players: Vec<PlayerSynergy>,
+    pub pairs: Vec<PairSynergy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerSynergy {
+    pub player_id: String,
+    pub name: String,
+    pub matches_together: i32,
+    pub wins_together: i32,
+    pub win_rate_together: f64,
+    pub avg_kills_together: f64,
+    pub avg_placement_together: f64,
+    pub matches_apart: i32, // matches where no other group member was on their roster
+    pub wins_apart: i32,
+    pub win_rate_apart: f64,
+    pub avg_kills_apart: f64,
+    pub avg_placement_apart: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairSynergy {
+    pub player_ids: [String; 2],
+    pub names: [String; 2],
+    pub matches_together: i32,
+    pub wins_together: i32,
+    pub win_rate_together: f64,
+    pub avg_team_placement: f64,
+}
+
+/// Someone a player shared a roster with, tracked or not.
+#[derive(Debug, Clone, Serialize)]
+pub struct Teammate {
+    pub account_id: String,
+    pub name: String, // name in the most recent match played together
+    pub player_id: Option<String>, // set when the teammate is a tracked player
+    pub tracked: bool,
+    pub matches_together: i32,
+    pub wins_together: i32,
+    pub win_rate_together: f64,
+    pub avg_kills: f64, // the teammate's kills per match played together
+    pub avg_team_placement: f64,
+    pub last_played_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeammatesResponse {
+    pub player_id: String,
+    pub period: String,
+    pub mode: String,
+    pub teammates: Vec<Teammate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SynergyResponse {
+    pub period: String,
+    pub mode: String,
+    #[serde(flatten)]
+    pub stats: SynergyStats,
+}
+
+// Totals of one series of matches (a player together or apart, a pair, the whole group)
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    matches: i32,
+    wins: i32,
+    kills: i32,
+    placement_sum: i64,
+}
+
+impl Tally {
+    fn add(&mut self, placement: i32, kills: i32) {
+        self.matches += 1;
+        self.kills += kills;
+        self.placement_sum += placement as i64;
+        if placement == 1 {
+            self.wins += 1;
+        }
+    }
+
+    fn win_rate(&self) -> f64 {
+        percent(self.wins, self.matches)
+    }
+
+    fn avg_kills(&self) -> f64 {
+        self.average(self.kills as i64)
+    }
+
+    fn avg_placement(&self) -> f64 {
+        self.average(self.placement_sum)
+    }
+
+    fn average(&self, total: i64) -> f64 {
+        if self.matches > 0 {
+            total as f64 / self.matches as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Teammate {
+    /// Lists the human teammates of the player across the matches of the period and mode, most
+    /// frequent first. Tracking information is left for the caller to fill in.
+    pub fn from_matches(
+        account_id: &str,
+        matches: &[PubgMatchResponse],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> Vec<Teammate> {
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let mut teammates: HashMap<String, (Tally, String, String)> = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for match_data in matches {
+            if !seen.insert(match_data.data.id.as_str())
+                || !in_scope(match_data, mode, period_start, period_end)
+            {
+                continue;
+            }
+
+            let Some(team) = match_data.team_of(account_id) else {
+                continue;
+            };
+
+            let created_at = &match_data.data.attributes.created_at;
+            let others = team
+                .members
+                .iter()
+                .filter(|m| m.player_id != account_id && !is_bot(&m.player_id));
+            for member in others {
+                let (tally, name, last_played_at) = teammates
+                    .entry(member.player_id.clone())
+                    .or_insert_with(|| (Tally::default(), String::new(), String::new()));
+                tally.add(team.rank, member.kills);
+
+                // RFC 3339 dates in UTC sort chronologically as strings
+                if *created_at > *last_played_at {
+                    *last_played_at = created_at.clone();
+                    *name = member.name.clone();
+                }
+            }
+        }
+
+        let mut teammates: Vec<Teammate> = teammates
+            .into_iter()
+            .map(|(account_id, (tally, name, last_played_at))| Teammate {
+                account_id,
+                name,
+                player_id: None,
+                tracked: false,
+                matches_together: tally.matches,
+                wins_together: tally.wins,
+                win_rate_together: tally.win_rate(),
+                avg_kills: tally.avg_kills(),
+                avg_team_placement: tally.avg_placement(),
+                last_played_at,
+            })
+            .collect();
+
+        teammates.sort_by(|a, b| {
+            b.matches_together
+                .cmp(&a.matches_together)
+                .then_with(|| b.last_played_at.cmp(&a.last_played_at))
+                .then_with(|| a.account_id.cmp(&b.account_id))
+        });
+        teammates
+    }
+}
+
+impl SynergyStats {
+    /// Aggregates the matches of the period and mode for the given group. Matches listed more
+    /// than once are counted a single time.
+    pub fn compute(
+        members: &[SynergyMember],
+        matches: &[PubgMatchResponse],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> Self {
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let index: HashMap<&str, usize> = members
+            .iter()
+            .enumerate()
+            .map(|(i, member)| (member.account_id.as_str(), i))
+            .collect();
+
+        let mut group = Tally::default();
+        let mut combined_damage = 0.0;
+        let mut together = vec![Tally::default(); members.len()];
+        let mut apart = vec![Tally::default(); members.len()];
+        let mut pairs: HashMap<(usize, usize), Tally> = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for match_data in matches {
+            if !seen.insert(match_data.data.id.as_str())
+                || !in_scope(match_data, mode, period_start, period_end)
+            {
+                continue;
+            }
+
+            for team in match_data.teams() {
+                let in_group: Vec<(usize, i32, f64)> = team
+                    .members
+                    .iter()
+                    .filter_map(|m| {
+                        index
+                            .get(m.player_id.as_str())
+                            .map(|&i| (i, m.kills, m.damage_dealt))
+                    })
+                    .collect();
+
+                match in_group.as_slice() {
+                    [] => {}
+                    [(i, kills, _)] => apart[*i].add(team.rank, *kills),
+                    _ => {
+                        let kills: i32 = in_group.iter().map(|(_, kills, _)| kills).sum();
+                        group.add(team.rank, kills);
+                        combined_damage +=
+                            in_group.iter().map(|(_, _, damage)| damage).sum::<f64>();
+
+                        for (n, (i, member_kills, _)) in in_group.iter().enumerate() {
+                            together[*i].add(team.rank, *member_kills);
+                            for (j, _, _) in &in_group[n + 1..] {
+                                let key = (*i.min(j), *i.max(j));
+                                pairs.entry(key).or_default().add(team.rank, kills);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let players = members
+            .iter()
+            .zip(together.iter().zip(&apart))
+            .map(|(member, (together, apart))| PlayerSynergy {
+                player_id: member.player_id.clone(),
+                name: member.name.clone(),
+                matches_together: together.matches,
+                wins_together: together.wins,
+                win_rate_together: together.win_rate(),
+                avg_kills_together: together.avg_kills(),
+                avg_placement_together: together.avg_placement(),
+                matches_apart: apart.matches,
+                wins_apart: apart.wins,
+                win_rate_apart: apart.win_rate(),
+                avg_kills_apart: apart.avg_kills(),
+                avg_placement_apart: apart.avg_placement(),
+            })
+            .collect();
+
+        let mut pairs: Vec<PairSynergy> = pairs
+            .into_iter()
+            .map(|((i, j), tally)| PairSynergy {
+                player_ids: [members[i].player_id.clone(), members[j].player_id.clone()],
+                names: [members[i].name.clone(), members[j].name.clone()],
+                matches_together: tally.matches,
+                wins_together: tally.wins,
+                win_rate_together: tally.win_rate(),
+                avg_team_placement: tally.avg_placement(),
+            })
+            .collect();
+        pairs.sort_by(|a, b| {
+            b.matches_together
+                .cmp(&a.matches_together)
+                .then_with(|| a.player_ids.cmp(&b.player_ids))
+        });
+
+        SynergyStats {
+            matches_together: group.matches,
+            wins_together: group.wins,
+            win_rate_together: group.win_rate(),
+            avg_team_placement: group.avg_placement(),
+            combined_kills: group.kills,
+            avg_combined_kills: group.avg_kills(),
+            combined_damage,
+            players,
+            pairs,
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+// PUBG telemetry events. Only the events and fields we use are modeled, every other event
+// is read as `Other`. Distances are in centimeters, as sent by PUBG.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "_T")]
+pub enum TelemetryEvent {
+    LogPlayerKillV2(Box<LogPlayerKillV2>),
+    LogPlayerTakeDamage(Box<LogPlayerTakeDamage>),
+    LogPlayerMakeGroggy(Box<LogPlayerMakeGroggy>),
+    LogParachuteLanding(LogParachuteLanding),
+    LogPlayerPosition(LogPlayerPosition),
+    LogItemPickup(LogItemPickup),
+    LogMatchEnd(LogMatchEnd),
+    LogMatchStart(LogMatchStart),
+    LogPlayerRevive(Box<LogPlayerRevive>),
+    LogVehicleDestroy(Box<LogVehicleDestroy>),
+    LogPhaseChange(LogPhaseChange),
+    LogGameStatePeriodic(LogGameStatePeriodic),
+    #[serde(other)]
+    Other,
+}
+
+impl TelemetryEvent {
+    /// Event date, `None` for the events we don't model.
+    pub fn timestamp(&self) -> Option<&str> {
+        match self {
+            TelemetryEvent::LogPlayerKillV2(e) => Some(&e.timestamp),
+            TelemetryEvent::LogPlayerTakeDamage(e) => Some(&e.timestamp),
+            TelemetryEvent::LogPlayerMakeGroggy(e) => Some(&e.timestamp),
+            TelemetryEvent::LogParachuteLanding(e) => Some(&e.timestamp),
+            TelemetryEvent::LogPlayerPosition(e) => Some(&e.timestamp),
+            TelemetryEvent::LogItemPickup(e) => Some(&e.timestamp),
+            TelemetryEvent::LogMatchEnd(e) => Some(&e.timestamp),
+            TelemetryEvent::LogMatchStart(e) => Some(&e.timestamp),
+            TelemetryEvent::LogPlayerRevive(e) => Some(&e.timestamp),
+            TelemetryEvent::LogVehicleDestroy(e) => Some(&e.timestamp),
+            TelemetryEvent::LogPhaseChange(e) => Some(&e.timestamp),
+            TelemetryEvent::LogGameStatePeriodic(e) => Some(&e.timestamp),
+            TelemetryEvent::Other => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryLocation {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryCharacter {
+    pub name: String,
+    #[serde(rename = "teamId")]
+    pub team_id: i32,
+    pub health: f64,
+    pub location: TelemetryLocation,
+    #[serde(default)]
+    pub ranking: i32,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "isInBlueZone", default)]
+    pub is_in_blue_zone: bool,
+    #[serde(rename = "isInRedZone", default)]
+    pub is_in_red_zone: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryDamageInfo {
+    #[serde(rename = "damageReason")]
+    pub damage_reason: String, // "HeadShot", "TorsoShot", ...
+    #[serde(rename = "damageTypeCategory")]
+    pub damage_type_category: String, // "Damage_Gun", "Damage_Explosion_Grenade", ...
+    #[serde(rename = "damageCauserName")]
+    pub damage_causer_name: String, // weapon or vehicle, e.g. "WeapHK416_C"
+    #[serde(rename = "additionalInfo")]
+    pub additional_info: Vec<String>,
+    pub distance: f64,
+    #[serde(rename = "isThroughPenetrableWall")]
+    pub is_through_penetrable_wall: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPlayerKillV2 {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "attackId")]
+    pub attack_id: i64,
+    pub victim: TelemetryCharacter,
+    // Knocked down then finished: the player who knocked the victim
+    #[serde(rename = "dBNOMaker")]
+    pub dbno_maker: Option<TelemetryCharacter>,
+    #[serde(rename = "dBNODamageInfo", default, deserialize_with = "null_as_default")]
+    pub dbno_damage_info: TelemetryDamageInfo,
+    pub finisher: Option<TelemetryCharacter>,
+    #[serde(rename = "finishDamageInfo", default, deserialize_with = "null_as_default")]
+    pub finish_damage_info: TelemetryDamageInfo,
+    // Player credited with the kill, missing for deaths to the zone or a fall
+    pub killer: Option<TelemetryCharacter>,
+    #[serde(rename = "killerDamageInfo", default, deserialize_with = "null_as_default")]
+    pub killer_damage_info: TelemetryDamageInfo,
+    #[serde(rename = "assists_AccountId", default)]
+    pub assists_account_id: Vec<String>,
+    #[serde(rename = "teamKillers_AccountId", default)]
+    pub team_killers_account_id: Vec<String>,
+    #[serde(rename = "isSuicide", default)]
+    pub is_suicide: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPlayerTakeDamage {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "attackId")]
+    pub attack_id: i64,
+    pub attacker: Option<TelemetryCharacter>,
+    pub victim: TelemetryCharacter,
+    #[serde(rename = "damageTypeCategory")]
+    pub damage_type_category: String,
+    #[serde(rename = "damageReason")]
+    pub damage_reason: String,
+    pub damage: f64,
+    #[serde(rename = "damageCauserName")]
+    pub damage_causer_name: String,
+    #[serde(rename = "isThroughPenetrableWall", default)]
+    pub is_through_penetrable_wall: bool,
+}
+
+// A player knocked down (DBNO)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPlayerMakeGroggy {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "attackId")]
+    pub attack_id: i64,
+    pub attacker: Option<TelemetryCharacter>,
+    pub victim: TelemetryCharacter,
+    #[serde(rename = "damageReason", default)]
+    pub damage_reason: String,
+    #[serde(rename = "damageTypeCategory", default)]
+    pub damage_type_category: String,
+    #[serde(rename = "damageCauserName", default)]
+    pub damage_causer_name: String,
+    #[serde(default)]
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogParachuteLanding {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    pub character: TelemetryCharacter,
+    pub distance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryVehicle {
+    #[serde(rename = "vehicleType", default)]
+    pub vehicle_type: String,
+    #[serde(rename = "vehicleId", default)]
+    pub vehicle_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPlayerPosition {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    pub character: TelemetryCharacter,
+    pub vehicle: Option<TelemetryVehicle>,
+    #[serde(rename = "elapsedTime")]
+    pub elapsed_time: f64, // seconds since the start of the match
+    #[serde(rename = "numAlivePlayers")]
+    pub num_alive_players: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryItem {
+    #[serde(rename = "itemId")]
+    pub item_id: String, // e.g. "Item_Weapon_HK416_C"
+    #[serde(rename = "stackCount", default)]
+    pub stack_count: i32,
+    #[serde(default)]
+    pub category: String, // "Weapon", "Attachment", "Use", ...
+    #[serde(rename = "subCategory", default)]
+    pub sub_category: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogItemPickup {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    pub character: TelemetryCharacter,
+    pub item: TelemetryItem,
+}
+
+// Recent telemetry wraps each character with its end-of-match weapon info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TelemetryMatchEndCharacter {
+    Wrapped { character: TelemetryCharacter },
+    Plain(TelemetryCharacter),
+}
+
+impl TelemetryMatchEndCharacter {
+    pub fn character(&self) -> &TelemetryCharacter {
+        match self {
+            TelemetryMatchEndCharacter::Wrapped { character } => character,
+            TelemetryMatchEndCharacter::Plain(character) => character,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogMatchEnd {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(default)]
+    pub characters: Vec<TelemetryMatchEndCharacter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogMatchStart {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "mapName", default)]
+    pub map_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPlayerRevive {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    pub reviver: TelemetryCharacter,
+    pub victim: TelemetryCharacter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogVehicleDestroy {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "attackId", default)]
+    pub attack_id: i64,
+    pub attacker: Option<TelemetryCharacter>,
+    pub vehicle: Option<TelemetryVehicle>,
+    #[serde(rename = "damageTypeCategory", default)]
+    pub damage_type_category: String,
+    #[serde(rename = "damageCauserName", default)]
+    pub damage_causer_name: String,
+    #[serde(default)]
+    pub distance: f64,
+}
+
+// Start of a new zone phase, the circle starts shrinking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogPhaseChange {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    pub phase: i32,
+    #[serde(rename = "elapsedTime", default)]
+    pub elapsed_time: f64,
+}
+
+// Zone state, sent every few seconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryGameState {
+    #[serde(rename = "elapsedTime")]
+    pub elapsed_time: f64,
+    #[serde(rename = "numAlivePlayers")]
+    pub num_alive_players: i32,
+    // Current circle (blue zone border)
+    #[serde(rename = "safetyZonePosition")]
+    pub safety_zone_position: TelemetryLocation,
+    #[serde(rename = "safetyZoneRadius")]
+    pub safety_zone_radius: f64,
+    // Next circle (white circle)
+    #[serde(rename = "poisonGasWarningPosition")]
+    pub poison_gas_warning_position: TelemetryLocation,
+    #[serde(rename = "poisonGasWarningRadius")]
+    pub poison_gas_warning_radius: f64,
+    #[serde(rename = "redZonePosition")]
+    pub red_zone_position: TelemetryLocation,
+    #[serde(rename = "redZoneRadius")]
+    pub red_zone_radius: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogGameStatePeriodic {
+    #[serde(rename = "_D")]
+    pub timestamp: String,
+    #[serde(rename = "gameState")]
+    pub game_state: TelemetryGameState,
+}
+
+// Some telemetry versions send `null` instead of an empty object
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
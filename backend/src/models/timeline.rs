@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::models::telemetry::{TelemetryCharacter, TelemetryEvent, TelemetryLocation};
+
+/// A position on the map, in meters from its north-west corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl From<&TelemetryLocation> for Position {
+    fn from(location: &TelemetryLocation) -> Self {
+        Position {
+            x: location.x / 100.0,
+            y: location.y / 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    Knock,          // a tracked player knocked someone down
+    Knocked,        // a tracked player was knocked down
+    Kill,           // a tracked player killed someone
+    Death,          // a tracked player died
+    Revive,         // a tracked player revived someone, or was revived
+    VehicleDestroy, // a tracked player destroyed a vehicle
+    PhaseChange,    // a new zone phase started
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelinePlayer {
+    pub account_id: String,
+    pub name: String,
+    pub player_id: Option<String>, // set when the player is tracked
+    pub team_id: i32,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineZone {
+    pub phase: i32,
+    pub center: Position, // next safe zone
+    pub radius: f64,      // in meters
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEvent {
+    pub elapsed: f64, // seconds since the start of the match
+    pub kind: TimelineEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player: Option<TimelinePlayer>, // the one acting: killer, reviver, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<TimelinePlayer>, // the one acted upon: victim, revived player, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weapon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>, // in meters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vehicle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone: Option<TimelineZone>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchTimelineResponse {
+    pub match_id: String,
+    pub map_name: String,
+    pub game_mode: String,
+    pub created_at: String,
+    pub events: Vec<TimelineEvent>,
+}
+
+/// Builds the timeline of a match from its telemetry, keeping the events that involve a
+/// tracked player and the zone phase changes.
+pub struct TimelineBuilder {
+    tracked: HashMap<String, String>, // account id -> player id
+    start: Option<DateTime<Utc>>,
+    zone: Option<(Position, f64)>, // last known next safe zone
+    events: Vec<TimelineEvent>,
+}
+
+impl TimelineBuilder {
+    /// `tracked` maps account ids of tracked players to their player id. `start` is used until
+    /// the telemetry tells when the match started.
+    pub fn new(tracked: HashMap<String, String>, start: Option<DateTime<Utc>>) -> Self {
+        TimelineBuilder {
+            tracked,
+            start,
+            zone: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn add_event(&mut self, event: TelemetryEvent) {
+        match event {
+            TelemetryEvent::LogMatchStart(start) => {
+                if let Some(date) = parse_date(&start.timestamp) {
+                    self.start = Some(date);
+                }
+            }
+            TelemetryEvent::LogGameStatePeriodic(state) => {
+                let state = &state.game_state;
+                if state.poison_gas_warning_radius > 0.0 {
+                    self.zone = Some((
+                        Position::from(&state.poison_gas_warning_position),
+                        state.poison_gas_warning_radius / 100.0,
+                    ));
+                }
+            }
+            TelemetryEvent::LogPhaseChange(change) => {
+                let zone = self.zone.map(|(center, radius)| TimelineZone {
+                    phase: change.phase,
+                    center,
+                    radius,
+                });
+                self.push(&change.timestamp, TimelineEventKind::PhaseChange, |event| {
+                    event.zone = zone;
+                });
+            }
+            TelemetryEvent::LogPlayerMakeGroggy(knock) => {
+                let attacker = knock.attacker.as_ref();
+                let player = attacker.map(|a| self.player(a));
+                let target = Some(self.player(&knock.victim));
+
+                // Like kills, a knock between two tracked players shows up on both sides
+                let mut kinds = Vec::new();
+                if attacker.is_some_and(|a| self.is_tracked(a)) {
+                    kinds.push(TimelineEventKind::Knock);
+                }
+                if self.is_tracked(&knock.victim) {
+                    kinds.push(TimelineEventKind::Knocked);
+                }
+
+                for kind in kinds {
+                    self.push(&knock.timestamp, kind, |event| {
+                        event.player = player.clone();
+                        event.target = target.clone();
+                        event.weapon = non_empty(&knock.damage_causer_name);
+                        event.distance = Some(knock.distance / 100.0);
+                    });
+                }
+            }
+            TelemetryEvent::LogPlayerKillV2(kill) => {
+                let killer = kill.killer.as_ref();
+                let player = killer.map(|k| self.player(k));
+                let target = Some(self.player(&kill.victim));
+                let info = &kill.killer_damage_info;
+
+                // A kill between two tracked players shows up on both sides
+                let mut kinds = Vec::new();
+                if killer.is_some_and(|k| self.is_tracked(k)) {
+                    kinds.push(TimelineEventKind::Kill);
+                }
+                if self.is_tracked(&kill.victim) {
+                    kinds.push(TimelineEventKind::Death);
+                }
+
+                for kind in kinds {
+                    self.push(&kill.timestamp, kind, |event| {
+                        event.player = player.clone();
+                        event.target = target.clone();
+                        event.weapon = non_empty(&info.damage_causer_name);
+                        event.distance = killer.map(|_| info.distance / 100.0);
+                    });
+                }
+            }
+            TelemetryEvent::LogPlayerRevive(revive) => {
+                if !self.is_tracked(&revive.reviver) && !self.is_tracked(&revive.victim) {
+                    return;
+                }
+
+                let player = Some(self.player(&revive.reviver));
+                let target = Some(self.player(&revive.victim));
+                self.push(&revive.timestamp, TimelineEventKind::Revive, |event| {
+                    event.player = player;
+                    event.target = target;
+                });
+            }
+            TelemetryEvent::LogVehicleDestroy(destroy) => {
+                let Some(attacker) = destroy.attacker.as_ref().filter(|a| self.is_tracked(a))
+                else {
+                    return;
+                };
+
+                let player = Some(self.player(attacker));
+                self.push(&destroy.timestamp, TimelineEventKind::VehicleDestroy, |event| {
+                    event.player = player;
+                    event.weapon = non_empty(&destroy.damage_causer_name);
+                    event.distance = Some(destroy.distance / 100.0);
+                    event.vehicle = destroy.vehicle.as_ref().map(|v| v.vehicle_id.clone());
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Events in chronological order.
+    pub fn finish(mut self) -> Vec<TimelineEvent> {
+        self.events.sort_by(|a, b| a.elapsed.total_cmp(&b.elapsed));
+        self.events
+    }
+
+    fn is_tracked(&self, character: &TelemetryCharacter) -> bool {
+        self.tracked.contains_key(&character.account_id)
+    }
+
+    fn player(&self, character: &TelemetryCharacter) -> TimelinePlayer {
+        TimelinePlayer {
+            account_id: character.account_id.clone(),
+            name: character.name.clone(),
+            player_id: self.tracked.get(&character.account_id).cloned(),
+            team_id: character.team_id,
+            position: Position::from(&character.location),
+        }
+    }
+
+    fn push(
+        &mut self,
+        timestamp: &str,
+        kind: TimelineEventKind,
+        fill: impl FnOnce(&mut TimelineEvent),
+    ) {
+        let elapsed = match (self.start, parse_date(timestamp)) {
+            (Some(start), Some(date)) => (date - start).num_milliseconds() as f64 / 1000.0,
+            _ => 0.0,
+        };
+
+        let mut event = TimelineEvent {
+            elapsed,
+            kind,
+            player: None,
+            target: None,
+            weapon: None,
+            distance: None,
+            vehicle: None,
+            zone: None,
+        };
+        fill(&mut event);
+        self.events.push(event);
+    }
+}
+
+fn parse_date(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -0,0 +1,214 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::models::telemetry::{TelemetryEvent, TelemetryLocation};
+
+// Positions are sent every 10 seconds for every player and the zone state even more often,
+// tracks are down-sampled and delta-encoded to keep the response small: each array holds
+// its first value, then the difference with the previous value. Times are in seconds since
+// the start of the match, positions and radiuses in whole meters.
+
+/// Movements of one player over the match.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerTrack {
+    pub account_id: String,
+    pub name: String,
+    pub player_id: Option<String>,
+    pub team_id: i32,
+    pub t: Vec<i64>,
+    pub x: Vec<i64>,
+    pub y: Vec<i64>,
+}
+
+/// Zone state over the match: the blue zone border, the next safe zone (white circle) and
+/// the red zone (radius 0 when there is none).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ZoneTrack {
+    pub t: Vec<i64>,
+    pub blue_x: Vec<i64>,
+    pub blue_y: Vec<i64>,
+    pub blue_radius: Vec<i64>,
+    pub safe_x: Vec<i64>,
+    pub safe_y: Vec<i64>,
+    pub safe_radius: Vec<i64>,
+    pub red_x: Vec<i64>,
+    pub red_y: Vec<i64>,
+    pub red_radius: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchTracksResponse {
+    pub match_id: String,
+    pub map_name: String,
+    pub interval: u32, // seconds between two points
+    pub players: Vec<PlayerTrack>,
+    pub zones: ZoneTrack,
+}
+
+/// Replaces each value with its difference to the previous one, the first value is kept.
+pub fn delta_encode(values: &[i64]) -> Vec<i64> {
+    let mut previous = 0;
+    values
+        .iter()
+        .map(|value| {
+            let delta = value - previous;
+            previous = *value;
+            delta
+        })
+        .collect()
+}
+
+/// Reverse of `delta_encode`.
+pub fn delta_decode(deltas: &[i64]) -> Vec<i64> {
+    let mut current = 0;
+    deltas
+        .iter()
+        .map(|delta| {
+            current += delta;
+            current
+        })
+        .collect()
+}
+
+// Telemetry distances are in centimeters
+fn meters(value: f64) -> i64 {
+    (value / 100.0).round() as i64
+}
+
+#[derive(Default)]
+struct Samples {
+    name: String,
+    team_id: i32,
+    last: Option<f64>,
+    t: Vec<i64>,
+    x: Vec<i64>,
+    y: Vec<i64>,
+}
+
+#[derive(Default)]
+struct ZoneSamples {
+    last: Option<f64>,
+    values: [Vec<i64>; 10], // t, then blue, safe and red as x, y, radius
+}
+
+/// Collects the tracks of the tracked players and the zone state from the telemetry of a
+/// match, keeping at most one sample per `interval` seconds.
+pub struct TracksBuilder {
+    tracked: HashMap<String, String>, // account id -> player id
+    interval: f64,
+    players: HashMap<String, Samples>,
+    zones: ZoneSamples,
+}
+
+impl TracksBuilder {
+    pub fn new(tracked: HashMap<String, String>, interval: u32) -> Self {
+        TracksBuilder {
+            tracked,
+            interval: interval.max(1) as f64,
+            players: HashMap::new(),
+            zones: ZoneSamples::default(),
+        }
+    }
+
+    pub fn add_event(&mut self, event: TelemetryEvent) {
+        match event {
+            TelemetryEvent::LogPlayerPosition(position) => {
+                let character = position.character;
+                // Positions on the starting island come before the match starts
+                if position.elapsed_time <= 0.0 || !self.tracked.contains_key(&character.account_id)
+                {
+                    return;
+                }
+
+                let samples = self.players.entry(character.account_id).or_default();
+                if samples
+                    .last
+                    .is_some_and(|last| position.elapsed_time < last + self.interval)
+                {
+                    return;
+                }
+
+                samples.name = character.name;
+                samples.team_id = character.team_id;
+                samples.last = Some(position.elapsed_time);
+                samples.t.push(position.elapsed_time.round() as i64);
+                samples.x.push(meters(character.location.x));
+                samples.y.push(meters(character.location.y));
+            }
+            TelemetryEvent::LogGameStatePeriodic(state) => {
+                let state = state.game_state;
+                if state.elapsed_time <= 0.0
+                    || self
+                        .zones
+                        .last
+                        .is_some_and(|last| state.elapsed_time < last + self.interval)
+                {
+                    return;
+                }
+
+                self.zones.last = Some(state.elapsed_time);
+                let circles: [(&TelemetryLocation, f64); 3] = [
+                    (&state.safety_zone_position, state.safety_zone_radius),
+                    (&state.poison_gas_warning_position, state.poison_gas_warning_radius),
+                    (&state.red_zone_position, state.red_zone_radius),
+                ];
+
+                let values = &mut self.zones.values;
+                values[0].push(state.elapsed_time.round() as i64);
+                for (i, (center, radius)) in circles.into_iter().enumerate() {
+                    values[1 + i * 3].push(meters(center.x));
+                    values[2 + i * 3].push(meters(center.y));
+                    values[3 + i * 3].push(meters(radius));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Delta-encoded tracks, players sorted by team then name.
+    pub fn finish(self) -> (Vec<PlayerTrack>, ZoneTrack) {
+        let tracked = self.tracked;
+
+        let mut players: Vec<PlayerTrack> = self
+            .players
+            .into_iter()
+            .map(|(account_id, samples)| PlayerTrack {
+                player_id: tracked.get(&account_id).cloned(),
+                account_id,
+                name: samples.name,
+                team_id: samples.team_id,
+                t: delta_encode(&samples.t),
+                x: delta_encode(&samples.x),
+                y: delta_encode(&samples.y),
+            })
+            .collect();
+        players.sort_by(|a, b| a.team_id.cmp(&b.team_id).then_with(|| a.name.cmp(&b.name)));
+
+        let [
+            t,
+            blue_x,
+            blue_y,
+            blue_radius,
+            safe_x,
+            safe_y,
+            safe_radius,
+            red_x,
+            red_y,
+            red_radius,
+        ] = self.zones.values.map(|values| delta_encode(&values));
+        let zones = ZoneTrack {
+            t,
+            blue_x,
+            blue_y,
+            blue_radius,
+            safe_x,
+            safe_y,
+            safe_radius,
+            red_x,
+            red_y,
+            red_radius,
+        };
+
+        (players, zones)
+    }
+}
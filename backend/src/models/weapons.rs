@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{stats::percent, TelemetryEvent};
+
+/// Weapon totals of one player, summed over one or several matches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WeaponTotals {
+    pub kills: i32,
+    pub knocks: i32,
+    pub damage_dealt: f64,
+    pub headshot_kills: i32,
+    pub kill_distance: f64, // sum, in meters
+    pub longest_kill: f64,
+}
+
+impl WeaponTotals {
+    pub fn merge(&mut self, other: &WeaponTotals) {
+        self.kills += other.kills;
+        self.knocks += other.knocks;
+        self.damage_dealt += other.damage_dealt;
+        self.headshot_kills += other.headshot_kills;
+        self.kill_distance += other.kill_distance;
+        self.longest_kill = self.longest_kill.max(other.longest_kill);
+    }
+}
+
+/// Weapon totals of every player of a match, keyed by account id then weapon.
+#[derive(Debug, Clone, Default)]
+pub struct MatchWeapons {
+    pub players: HashMap<String, HashMap<String, WeaponTotals>>,
+}
+
+impl MatchWeapons {
+    /// Accounts for one telemetry event. Damage, knocks and kills on teammates are ignored.
+    pub fn add_event(&mut self, event: &TelemetryEvent) {
+        match event {
+            TelemetryEvent::LogPlayerKillV2(kill) => {
+                let Some(killer) = &kill.killer else { return };
+                if killer.team_id == kill.victim.team_id {
+                    return;
+                }
+
+                let info = &kill.killer_damage_info;
+                let distance = info.distance / 100.0;
+                let totals = self.totals(&killer.account_id, &info.damage_causer_name);
+                totals.kills += 1;
+                totals.kill_distance += distance;
+                totals.longest_kill = totals.longest_kill.max(distance);
+                if info.damage_reason == "HeadShot" {
+                    totals.headshot_kills += 1;
+                }
+            }
+            TelemetryEvent::LogPlayerMakeGroggy(knock) => {
+                let Some(attacker) = &knock.attacker else { return };
+                if attacker.team_id != knock.victim.team_id {
+                    self.totals(&attacker.account_id, &knock.damage_causer_name).knocks += 1;
+                }
+            }
+            TelemetryEvent::LogPlayerTakeDamage(damage) => {
+                let Some(attacker) = &damage.attacker else { return };
+                if attacker.team_id != damage.victim.team_id && damage.damage > 0.0 {
+                    self.totals(&attacker.account_id, &damage.damage_causer_name)
+                        .damage_dealt += damage.damage;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn totals(&mut self, account_id: &str, weapon: &str) -> &mut WeaponTotals {
+        self.players
+            .entry(account_id.to_string())
+            .or_default()
+            .entry(weapon.to_string())
+            .or_default()
+    }
+}
+
+// Weapon totals of every player of a match, as stored in the `match_weapons` collection.
+// Matches never change, so each telemetry is read a single time. Account ids contain dots and
+// can't be document keys, players are listed instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchWeaponsRecord {
+    #[serde(rename = "_id")]
+    pub id: String, // PUBG match id
+    pub shard: String,
+    #[serde(default)] // left out by projections when the player is not in the match
+    pub players: Vec<PlayerWeaponTotals>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerWeaponTotals {
+    pub account_id: String,
+    pub weapons: HashMap<String, WeaponTotals>,
+}
+
+impl MatchWeaponsRecord {
+    pub fn new(match_id: &str, shard: &str, weapons: &MatchWeapons) -> Self {
+        MatchWeaponsRecord {
+            id: match_id.to_string(),
+            shard: shard.to_string(),
+            players: weapons
+                .players
+                .iter()
+                .map(|(account_id, weapons)| PlayerWeaponTotals {
+                    account_id: account_id.clone(),
+                    weapons: weapons.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Weapon totals of the player in this match, `None` when they dealt no damage.
+    pub fn weapons_of(&self, account_id: &str) -> Option<&HashMap<String, WeaponTotals>> {
+        self.players
+            .iter()
+            .find(|p| p.account_id == account_id)
+            .map(|p| &p.weapons)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeaponStats {
+    pub weapon: String, // PUBG id, e.g. "WeapHK416_C"
+    pub name: String,   // e.g. "HK416"
+    pub kills: i32,
+    pub knocks: i32,
+    pub damage_dealt: f64,
+    pub headshot_kills: i32,
+    pub headshot_rate: f64, // percent of the kills
+    pub avg_kill_distance: f64, // in meters
+    pub longest_kill: f64,
+}
+
+impl WeaponStats {
+    pub fn new(weapon: &str, totals: &WeaponTotals) -> Self {
+        WeaponStats {
+            weapon: weapon.to_string(),
+            name: weapon_name(weapon),
+            kills: totals.kills,
+            knocks: totals.knocks,
+            damage_dealt: totals.damage_dealt,
+            headshot_kills: totals.headshot_kills,
+            headshot_rate: percent(totals.headshot_kills, totals.kills),
+            avg_kill_distance: if totals.kills > 0 {
+                totals.kill_distance / totals.kills as f64
+            } else {
+                0.0
+            },
+            longest_kill: totals.longest_kill,
+        }
+    }
+
+    /// Stats of every weapon, most kills (then damage) first.
+    pub fn from_totals(totals: &HashMap<String, WeaponTotals>) -> Vec<WeaponStats> {
+        let mut weapons: Vec<WeaponStats> = totals
+            .iter()
+            .map(|(weapon, totals)| WeaponStats::new(weapon, totals))
+            .collect();
+        weapons.sort_by(|a, b| {
+            b.kills
+                .cmp(&a.kills)
+                .then_with(|| b.damage_dealt.total_cmp(&a.damage_dealt))
+                .then_with(|| a.weapon.cmp(&b.weapon))
+        });
+        weapons
+    }
+}
+
+/// Readable name of a weapon id: "WeapHK416_C" gives "HK416".
+pub fn weapon_name(weapon: &str) -> String {
+    let name = weapon.strip_prefix("Weap").unwrap_or(weapon);
+    let name = name.strip_suffix("_C").unwrap_or(name);
+    name.to_string()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeaponStatsResponse {
+    pub player_id: String,
+    pub period: String,
+    pub mode: String,
+    pub matches_analyzed: usize, // matches of the period with telemetry
+    pub weapons: Vec<WeaponStats>,
+}
+
+/// One weapon compared across players.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeaponComparison {
+    pub weapon: String,
+    pub name: String,
+    pub total_kills: i32,
+    pub players: Vec<PlayerWeaponStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerWeaponStats {
+    pub player_id: String,
+    pub name: String,
+    pub stats: WeaponStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeaponComparisonResponse {
+    pub period: String,
+    pub mode: String,
+    pub weapons: Vec<WeaponComparison>, // most kills across players first
+}
+
+impl WeaponComparison {
+    /// Groups the weapon stats of several players by weapon.
+    pub fn compare(players: &[(String, String, Vec<WeaponStats>)]) -> Vec<WeaponComparison> {
+        let mut by_weapon: HashMap<&str, WeaponComparison> = HashMap::new();
+
+        for (player_id, name, weapons) in players {
+            for stats in weapons {
+                let comparison = by_weapon.entry(stats.weapon.as_str()).or_insert_with(|| {
+                    WeaponComparison {
+                        weapon: stats.weapon.clone(),
+                        name: stats.name.clone(),
+                        total_kills: 0,
+                        players: Vec::new(),
+                    }
+                });
+                comparison.total_kills += stats.kills;
+                comparison.players.push(PlayerWeaponStats {
+                    player_id: player_id.clone(),
+                    name: name.clone(),
+                    stats: stats.clone(),
+                });
+            }
+        }
+
+        let mut weapons: Vec<WeaponComparison> = by_weapon.into_values().collect();
+        weapons.sort_by(|a, b| {
+            b.total_kills
+                .cmp(&a.total_kills)
+                .then_with(|| a.weapon.cmp(&b.weapon))
+        });
+        weapons
+    }
+}
@@ -4,16 +4,29 @@ use axum::{
 };
 
 use crate::handlers::{
-    dashboard_handler,
+    dashboard_handler, landing_handler, match_handler,
     player_handler::{self, AppState},
+    rival_handler, season_handler, synergy_handler, weapon_handler,
 };
 
 pub fn create_api_routes() -> Router<AppState> {
     Router::new()
         // Dashboard
         .route("/dashboard", get(dashboard_handler::get_dashboard_stats))
+        .route("/dashboard/weapons", get(weapon_handler::get_weapon_comparison))
+        // Landings
+        .route("/landings", get(landing_handler::get_group_landings))
+        // Matches
+        .route("/matches/:id", get(match_handler::get_match))
+        .route("/matches/:id/timeline", get(match_handler::get_match_timeline))
+        .route("/matches/:id/tracks", get(match_handler::get_match_tracks))
+        // Ranked
+        .route("/ranked", get(season_handler::get_ranked_standings))
+        // Synergy
+        .route("/synergy", get(synergy_handler::get_synergy))
         // Stats
         .route("/stats/clear-cache", post(player_handler::clear_all_stats_cache))
+        .route("/stats/metrics", get(player_handler::get_stats_metrics))
         // Players
         .route("/players", post(player_handler::create_player))
         .route("/players", get(player_handler::get_players))
@@ -23,4 +36,19 @@ pub fn create_api_routes() -> Router<AppState> {
         .route("/players/:id/refresh", post(player_handler::refresh_player))
         .route("/players/:id", delete(player_handler::delete_player))
         .route("/players/:id/matches", get(player_handler::get_player_matches))
+        .route("/players/:id/matches/history", get(match_handler::get_player_match_history))
+        .route("/players/:id/seasons/:season", get(season_handler::get_player_season_stats))
+        .route(
+            "/players/:id/seasons/:season/ranked",
+            get(season_handler::get_player_ranked_stats),
+        )
+        .route("/players/:id/lifetime", get(season_handler::get_player_lifetime_stats))
+        .route("/players/:id/teammates", get(synergy_handler::get_teammates))
+        .route(
+            "/players/:id/teammates/:account_id/track",
+            post(synergy_handler::track_teammate),
+        )
+        .route("/players/:id/weapons", get(weapon_handler::get_player_weapons))
+        .route("/players/:id/landings", get(landing_handler::get_player_landings))
+        .route("/players/:id/rivals", get(rival_handler::get_rivals))
 }
@@ -0,0 +1,213 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use moka::future::Cache;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    db::{KillRepository, MatchRepository, MongoDb, PlayerRepository},
+    models::{
+        stats::{is_bot, BotEncounters},
+        telemetry::LogPlayerKillV2,
+        KillRecord, MatchRecord, Player, Rivals, TelemetryEvent,
+    },
+    services::{
+        match_service::MATCH_RETENTION_DAYS, pubg_api_service::PubgApiError,
+        telemetry_service::TELEMETRY_CONCURRENCY, TelemetryError, TelemetryService,
+    },
+};
+
+pub struct KillService {
+    db: Arc<MongoDb>,
+    telemetry_service: Arc<TelemetryService>,
+    // Matches whose telemetry could not be read (expired, missing), not worth retrying soon
+    unavailable: Cache<String, ()>,
+}
+
+impl KillService {
+    pub fn new(db: Arc<MongoDb>, telemetry_service: Arc<TelemetryService>) -> Self {
+        let unavailable = Cache::builder()
+            .max_capacity(10_000)
+            .time_to_live(std::time::Duration::from_secs(24 * 3600))
+            .build();
+
+        KillService {
+            db,
+            telemetry_service,
+            unavailable,
+        }
+    }
+
+    /// Stores the kills of the player's recent matches that don't have them yet, from their
+    /// telemetry. Only matches PUBG still serves are read, older ones have no telemetry left to
+    /// download. Downloads every missing telemetry file, so it is meant to run in the
+    /// background. Returns how many matches got their kills stored.
+    #[tracing::instrument(skip(self))]
+    pub async fn record_player(&self, account_id: &str) -> Result<usize, mongodb::error::Error> {
+        let since = Utc::now() - chrono::Duration::days(MATCH_RETENTION_DAYS);
+        let headers = MatchRepository::new(self.db.matches())
+            .find_headers_for_player(account_id, Some(since), None, None, None, false)
+            .await?;
+        let recorded = self.record_missing(&headers).await?;
+
+        tracing::info!("Kills of {} matches recorded", recorded);
+        Ok(recorded)
+    }
+
+    /// Stores the kills of the matches that don't have them yet, from their telemetry, reading
+    /// `TELEMETRY_CONCURRENCY` matches at a time. Returns how many matches got their kills
+    /// stored.
+    pub async fn record_missing(
+        &self,
+        headers: &[MatchRecord],
+    ) -> Result<usize, mongodb::error::Error> {
+        let repo = KillRepository::new(self.db.kills(), self.db.kill_matches());
+        let match_ids: Vec<String> = headers.iter().map(|h| h.id.clone()).collect();
+        let recorded = repo.find_recorded_match_ids(&match_ids).await?;
+
+        // Owned headers, borrowed ones don't make a `Send` future here
+        let missing: Vec<MatchRecord> = headers
+            .iter()
+            .filter(|h| !recorded.contains(&h.id) && !self.unavailable.contains_key(&h.id))
+            .cloned()
+            .collect();
+        let mut results = stream::iter(missing)
+            .map(|header| async move {
+                let result = self.read_kills(&header).await;
+                (header, result)
+            })
+            .buffer_unordered(TELEMETRY_CONCURRENCY);
+
+        let mut count = 0;
+        while let Some((header, result)) = results.next().await {
+            match result {
+                Ok(kills) => {
+                    repo.save_match(&header.id, &kills).await?;
+                    count += 1;
+                }
+                Err(e) => {
+                    // Log the error but continue with other matches
+                    tracing::warn!("Skipping kills of match {}: {}", header.id, e);
+                    let gone = matches!(
+                        e,
+                        TelemetryError::MatchNotFound(_)
+                            | TelemetryError::NoTelemetry(_)
+                            | TelemetryError::Api(PubgApiError::NotFound(_))
+                    );
+                    if gone {
+                        self.unavailable.insert(header.id.clone(), ()).await;
+                    }
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Who kills the player the most and who they kill the most, over every stored match
+    /// they played whose kills are recorded. Reads the database only, kills are recorded in
+    /// the background when the player is refreshed.
+    #[tracing::instrument(skip(self, player), fields(player_name = %player.name))]
+    pub async fn get_rivals(
+        &self,
+        player: &Player,
+        limit: usize,
+    ) -> Result<(usize, Rivals), mongodb::error::Error> {
+        let match_ids: Vec<String> = MatchRepository::new(self.db.matches())
+            .find_headers_for_player(&player.account_id, None, None, None, None, false)
+            .await?
+            .into_iter()
+            .map(|header| header.id)
+            .collect();
+        let matches_analyzed = KillRepository::new(self.db.kills(), self.db.kill_matches())
+            .find_recorded_match_ids(&match_ids)
+            .await?
+            .len();
+
+        let kills = KillRepository::new(self.db.kills(), self.db.kill_matches())
+            .find_for_account(&player.account_id, None)
+            .await?;
+
+        let mut opponents: Vec<String> = kills
+            .iter()
+            .filter_map(|kill| kill.killer.as_ref())
+            .chain(kills.iter().map(|kill| &kill.victim))
+            .map(|participant| participant.account_id.clone())
+            .filter(|account_id| *account_id != player.account_id)
+            .collect();
+        opponents.sort();
+        opponents.dedup();
+
+        let tracked: HashMap<String, String> = PlayerRepository::new(self.db.players())
+            .find_by_account_ids(&opponents)
+            .await?
+            .into_iter()
+            .filter_map(|p| p.id.map(|id| (p.account_id, id.to_hex())))
+            .collect();
+
+        tracing::info!(
+            "{} kills over {} matches, {} tracked opponents",
+            kills.len(),
+            matches_analyzed,
+            tracked.len()
+        );
+        Ok((
+            matches_analyzed,
+            Rivals::compute(&player.account_id, &kills, &tracked, limit),
+        ))
+    }
+
+    /// Bots the player killed and whether a bot killed them, for each of the matches whose
+    /// kills are recorded. Reads the database only, matches without recorded kills are left
+    /// out.
+    pub async fn get_bot_encounters(
+        &self,
+        account_id: &str,
+        match_ids: &[String],
+    ) -> Result<HashMap<String, BotEncounters>, mongodb::error::Error> {
+        let recorded: Vec<String> = KillRepository::new(self.db.kills(), self.db.kill_matches())
+            .find_recorded_match_ids(match_ids)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut encounters: HashMap<String, BotEncounters> = recorded
+            .iter()
+            .map(|id| (id.clone(), BotEncounters::default()))
+            .collect();
+        let kills = KillRepository::new(self.db.kills(), self.db.kill_matches())
+            .find_for_account(account_id, Some(&recorded))
+            .await?;
+
+        for kill in &kills {
+            let Some(killer) = &kill.killer else { continue };
+            let Some(entry) = encounters.get_mut(&kill.match_id) else { continue };
+
+            if killer.account_id == account_id && is_bot(&kill.victim.account_id) {
+                entry.bot_kills += 1;
+                if kill.damage_reason == "HeadShot" {
+                    entry.bot_headshot_kills += 1;
+                }
+            } else if kill.victim.account_id == account_id && is_bot(&killer.account_id) {
+                entry.killed_by_bot = true;
+            }
+        }
+
+        Ok(encounters)
+    }
+
+    async fn read_kills(&self, header: &MatchRecord) -> Result<Vec<KillRecord>, TelemetryError> {
+        let kills: Vec<Box<LogPlayerKillV2>> = self
+            .telemetry_service
+            .fold(&header.shard, &header.id, Vec::new(), |kills, event| {
+                if let TelemetryEvent::LogPlayerKillV2(kill) = event {
+                    kills.push(kill);
+                }
+            })
+            .await?;
+
+        Ok(kills
+            .iter()
+            .map(|kill| KillRecord::new(header, kill))
+            .collect())
+    }
+}
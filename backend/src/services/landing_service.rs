@@ -0,0 +1,184 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    db::{LandingRepository, MatchRepository, MongoDb},
+    models::{
+        telemetry::TelemetryLocation, LandingRecord, MatchRecord, ModeFilter, Player,
+        StatsPeriod, TelemetryEvent,
+    },
+    services::{
+        telemetry_service::TELEMETRY_CONCURRENCY, MatchService, TelemetryError, TelemetryService,
+    },
+};
+
+pub struct LandingService {
+    db: Arc<MongoDb>,
+    match_service: Arc<MatchService>,
+    telemetry_service: Arc<TelemetryService>,
+}
+
+impl LandingService {
+    pub fn new(
+        db: Arc<MongoDb>,
+        match_service: Arc<MatchService>,
+        telemetry_service: Arc<TelemetryService>,
+    ) -> Self {
+        LandingService {
+            db,
+            match_service,
+            telemetry_service,
+        }
+    }
+
+    /// Landings of a group of tracked players over the stored matches of the period and mode,
+    /// on one map only when `map_name` is given. Landings not recorded yet are read from the
+    /// match telemetry and stored.
+    #[tracing::instrument(skip(self, players, period, mode), fields(players = players.len(), period = %period.label(), mode = %mode.label()))]
+    pub async fn get_landings(
+        &self,
+        players: &[Player],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+        map_name: Option<&str>,
+    ) -> Result<Vec<LandingRecord>, mongodb::error::Error> {
+        // Shared matches come back once
+        let accounts: HashSet<&str> = players.iter().map(|p| p.account_id.as_str()).collect();
+        let account_ids: Vec<&str> = accounts.iter().copied().collect();
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let headers = MatchRepository::new(self.db.matches())
+            .find_headers_for_players(
+                &account_ids,
+                Some(period_start),
+                Some(period_end),
+                map_name,
+                mode.game_mode_pattern(),
+            )
+            .await?;
+
+        // One landing per tracked player of each match
+        let expected: Vec<(&MatchRecord, Vec<String>)> = headers
+            .iter()
+            .map(|header| {
+                let tracked = header
+                    .participant_ids
+                    .iter()
+                    .filter(|account_id| accounts.contains(account_id.as_str()))
+                    .cloned()
+                    .collect();
+                (header, tracked)
+            })
+            .collect();
+        let keys: Vec<String> = expected
+            .iter()
+            .flat_map(|(header, tracked)| {
+                tracked
+                    .iter()
+                    .map(|account_id| LandingRecord::key(&header.id, account_id))
+            })
+            .collect();
+
+        let repo = LandingRepository::new(self.db.landings());
+        let mut landings = repo.find_by_ids(&keys).await?;
+        let stored: HashSet<String> = landings.iter().map(|l| l.id.clone()).collect();
+
+        // Only the matches with landings to record are loaded whole
+        let missing: Vec<(String, String, Vec<String>)> = expected
+            .into_iter()
+            .map(|(header, tracked)| {
+                let missing: Vec<String> = tracked
+                    .into_iter()
+                    .filter(|account_id| {
+                        !stored.contains(&LandingRecord::key(&header.id, account_id))
+                    })
+                    .collect();
+                (header.shard.clone(), header.id.clone(), missing)
+            })
+            .filter(|(_, _, missing)| !missing.is_empty())
+            .collect();
+
+        let repo = &repo;
+        let mut results = stream::iter(missing)
+            .map(|(shard, match_id, account_ids)| async move {
+                let result = self.record_landings(repo, &shard, &match_id, account_ids).await;
+                (match_id, result)
+            })
+            .buffer_unordered(TELEMETRY_CONCURRENCY);
+
+        let mut recorded = 0;
+        while let Some((match_id, result)) = results.next().await {
+            match result {
+                Ok(records) => {
+                    recorded += records.len();
+                    landings.extend(records);
+                }
+                Err(e) => {
+                    // Log the error but continue with other matches
+                    tracing::warn!("Skipping landings of match {}: {}", match_id, e);
+                }
+            }
+        }
+
+        tracing::info!(
+            "{} landings over {} matches, {} read from telemetry",
+            landings.len(),
+            headers.len(),
+            recorded
+        );
+        Ok(landings)
+    }
+
+    /// Reads where the given participants landed from the match telemetry and stores it.
+    async fn record_landings(
+        &self,
+        repo: &LandingRepository,
+        shard: &str,
+        match_id: &str,
+        account_ids: Vec<String>,
+    ) -> Result<Vec<LandingRecord>, TelemetryError> {
+        let match_data = self
+            .match_service
+            .get_match(shard, match_id)
+            .await
+            .map_err(|e| TelemetryError::Match(e.to_string()))?
+            .ok_or_else(|| TelemetryError::MatchNotFound(match_id.to_string()))?;
+        let wanted: HashSet<String> = account_ids.iter().cloned().collect();
+
+        let locations = self
+            .telemetry_service
+            .fold(
+                &match_data.data.attributes.shard_id,
+                &match_data.data.id,
+                HashMap::new(),
+                move |locations: &mut HashMap<String, TelemetryLocation>, event| {
+                    // The first landing counts, later ones come from vehicles or redeploys
+                    if let TelemetryEvent::LogParachuteLanding(landing) = event
+                        && wanted.contains(&landing.character.account_id)
+                        && !locations.contains_key(&landing.character.account_id)
+                    {
+                        locations.insert(landing.character.account_id, landing.character.location);
+                    }
+                },
+            )
+            .await?;
+
+        let records: Vec<LandingRecord> = account_ids
+            .iter()
+            .filter_map(|account_id| {
+                LandingRecord::new(&match_data, account_id, locations.get(account_id))
+            })
+            .collect();
+
+        for record in &records {
+            if let Err(e) = repo.save(record).await {
+                tracing::warn!("Failed to store landing {}: {}", record.id, e);
+            }
+        }
+
+        Ok(records)
+    }
+}
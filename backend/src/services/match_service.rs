@@ -0,0 +1,264 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use moka::future::Cache;
+use std::{collections::HashSet, sync::Arc};
+use tracing::Instrument;
+
+use crate::{
+    db::{MatchRepository, MongoDb},
+    models::{
+        MatchHistoryEntry, MatchHistoryFilter, MatchRecord, ModeFilter, Player,
+        PubgMatchResponse, StatsPeriod,
+    },
+    services::{pubg_api_service::PubgApiError, PubgApiService},
+};
+
+// Matches fetched at the same time from the PUBG API
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 8;
+
+// PUBG serves a match for this many days after it was played
+pub const MATCH_RETENTION_DAYS: i64 = 14;
+
+pub struct MatchService {
+    db: Arc<MongoDb>,
+    pubg_api: Arc<PubgApiService>,
+    // Ids the API no longer serves (older than PUBG's retention), not worth asking again
+    unavailable: Cache<String, ()>,
+    concurrency: usize,
+}
+
+impl MatchService {
+    pub fn new(db: Arc<MongoDb>, pubg_api: Arc<PubgApiService>) -> Self {
+        let unavailable = Cache::builder()
+            .max_capacity(10_000)
+            .time_to_live(std::time::Duration::from_secs(24 * 3600))
+            .build();
+
+        MatchService {
+            db,
+            pubg_api,
+            unavailable,
+            concurrency: DEFAULT_FETCH_CONCURRENCY,
+        }
+    }
+
+    /// Sets how many matches are fetched at the same time. Requests still go through the
+    /// rate limiter when the match endpoint is rate limited for the key.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Loads matches from the `matches` collection, fetching from the PUBG API (and storing)
+    /// only the ids that are not there yet. Matches that cannot be fetched are skipped.
+    #[tracing::instrument(skip(self, match_ids), fields(shard = %shard, requested = match_ids.len()))]
+    pub async fn get_matches(
+        &self,
+        shard: &str,
+        match_ids: &[String],
+    ) -> Result<Vec<PubgMatchResponse>, mongodb::error::Error> {
+        let repo = MatchRepository::new(self.db.matches());
+
+        let mut matches = Vec::with_capacity(match_ids.len());
+        let mut stored_ids = HashSet::new();
+
+        for record in repo.find_by_ids(match_ids).await? {
+            match record.to_response() {
+                Ok(match_data) => {
+                    stored_ids.insert(record.id.clone());
+                    matches.push(match_data);
+                }
+                Err(e) => {
+                    // Unreadable document, fetch it again below
+                    tracing::warn!("Failed to decode stored match {}: {}", record.id, e);
+                }
+            }
+        }
+
+        let missing: Vec<&String> = match_ids
+            .iter()
+            .filter(|id| !stored_ids.contains(*id) && !self.unavailable.contains_key(*id))
+            .collect();
+
+        tracing::info!(
+            "{} matches found in database, {} to fetch from PUBG API",
+            stored_ids.len(),
+            missing.len()
+        );
+
+        matches.extend(self.fetch_all(&repo, shard, &missing).await);
+
+        Ok(matches)
+    }
+
+    /// Loads a single match, from the database when stored. Returns `None` when PUBG does not
+    /// know the match (or no longer serves it).
+    pub async fn get_match(
+        &self,
+        shard: &str,
+        match_id: &str,
+    ) -> Result<Option<PubgMatchResponse>, Box<dyn std::error::Error + Send + Sync>> {
+        let repo = MatchRepository::new(self.db.matches());
+
+        if let Some(record) = repo.find_by_id(match_id).await? {
+            match record.to_response() {
+                Ok(match_data) => return Ok(Some(match_data)),
+                Err(e) => tracing::warn!("Failed to decode stored match {}: {}", match_id, e),
+            }
+        }
+
+        match self.fetch_and_store(&repo, shard, match_id).await {
+            Ok(match_data) => Ok(Some(match_data)),
+            Err(PubgApiError::NotFound(_)) => Ok(None),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    /// Fetches and stores the matches of `match_ids` that are not in the database yet.
+    pub async fn store_missing(
+        &self,
+        shard: &str,
+        match_ids: &[String],
+    ) -> Result<(), mongodb::error::Error> {
+        let repo = MatchRepository::new(self.db.matches());
+        let stored_ids = repo.find_stored_ids(match_ids).await?;
+
+        let missing: Vec<&String> = match_ids
+            .iter()
+            .filter(|id| !stored_ids.contains(*id) && !self.unavailable.contains_key(*id))
+            .collect();
+        self.fetch_all(&repo, shard, &missing).await;
+
+        Ok(())
+    }
+
+    /// Returns one page of the player's stored matches, most recent first unless asked
+    /// otherwise, together with the number of matches passing the filters. Matches are stored
+    /// when the player is added or refreshed, this only reads the database.
+    #[tracing::instrument(skip(self, player, filter), fields(player_name = %player.name))]
+    pub async fn get_player_history(
+        &self,
+        player: &Player,
+        filter: &MatchHistoryFilter,
+    ) -> Result<(u64, Vec<MatchHistoryEntry>), mongodb::error::Error> {
+        let (total, records) = MatchRepository::new(self.db.matches())
+            .find_history_page(&player.account_id, filter)
+            .await?;
+
+        let entries = records
+            .iter()
+            .filter_map(|record| match record.to_response() {
+                Ok(match_data) => MatchHistoryEntry::from_match(&match_data, &player.account_id),
+                Err(e) => {
+                    tracing::warn!("Failed to decode stored match {}: {}", record.id, e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok((total, entries))
+    }
+
+    /// Stored matches of the period and mode that any of the players took part in. Matches are
+    /// stored when players are added or refreshed, this only reads the database.
+    pub async fn get_matches_in_scope(
+        &self,
+        account_ids: &[&str],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> Result<Vec<PubgMatchResponse>, mongodb::error::Error> {
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let records = MatchRepository::new(self.db.matches())
+            .find_for_players(account_ids, period_start, period_end, mode.game_mode_pattern())
+            .await?;
+
+        Ok(records
+            .iter()
+            .filter_map(|record| match record.to_response() {
+                Ok(match_data) => Some(match_data),
+                Err(e) => {
+                    tracing::warn!("Failed to decode stored match {}: {}", record.id, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Fetches and stores matches, `concurrency` at a time. Matches that cannot be fetched are
+    /// logged and skipped.
+    #[tracing::instrument(skip(self, repo, match_ids), fields(total = match_ids.len(), done = 0, concurrency = self.concurrency))]
+    async fn fetch_all(
+        &self,
+        repo: &MatchRepository,
+        shard: &str,
+        match_ids: &[&String],
+    ) -> Vec<PubgMatchResponse> {
+        if match_ids.is_empty() {
+            return Vec::new();
+        }
+
+        let total = match_ids.len();
+        let mut done = 0;
+        let mut matches = Vec::with_capacity(total);
+
+        // Owned ids, borrowed ones don't make a `Send` future here
+        let ids: Vec<String> = match_ids.iter().map(|id| id.to_string()).collect();
+        let mut results = stream::iter(ids)
+            .map(|match_id| {
+                let span = tracing::info_span!("fetch_match", match_id = %match_id);
+                async move {
+                    let result = self.fetch_and_store(repo, shard, &match_id).await;
+                    (match_id, result)
+                }
+                .instrument(span)
+            })
+            .buffer_unordered(self.concurrency);
+
+        while let Some((match_id, result)) = results.next().await {
+            done += 1;
+            match result {
+                Ok(match_data) => matches.push(match_data),
+                // Log the error but continue with other matches
+                Err(e) => tracing::warn!("Failed to fetch match {}: {}", match_id, e),
+            }
+            tracing::Span::current().record("done", done);
+            tracing::debug!(done, total, "Match fetch progress");
+        }
+
+        tracing::info!("Fetched {} of {} matches from PUBG API", matches.len(), total);
+        matches
+    }
+
+    async fn fetch_and_store(
+        &self,
+        repo: &MatchRepository,
+        shard: &str,
+        match_id: &str,
+    ) -> Result<PubgMatchResponse, PubgApiError> {
+        let raw = match self.pubg_api.get_match_raw(shard, match_id).await {
+            Ok(raw) => raw,
+            Err(PubgApiError::NotFound(msg)) => {
+                tracing::debug!("Match {} is no longer available from PUBG API", match_id);
+                self.unavailable.insert(match_id.to_string(), ()).await;
+                return Err(PubgApiError::NotFound(msg));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let match_data: PubgMatchResponse = serde_json::from_value(raw.clone()).map_err(|e| {
+            PubgApiError::ServerError(format!("Failed to parse match {}: {}", match_id, e))
+        })?;
+
+        match MatchRecord::new(shard, &raw, &match_data) {
+            Ok(record) => {
+                if let Err(e) = repo.save(&record).await {
+                    tracing::warn!("Failed to store match {}: {}", match_id, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to convert match {} to BSON: {}", match_id, e),
+        }
+
+        tracing::debug!("Successfully fetched match {}", match_id);
+        Ok(match_data)
+    }
+}
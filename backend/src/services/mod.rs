@@ -1,8 +1,22 @@
 // Placeholder for services module
+pub mod kill_service;
+pub mod landing_service;
+pub mod match_service;
 pub mod player_service;
 pub mod pubg_api_service;
+pub mod season_service;
 pub mod stats_service;
+pub mod synergy_service;
+pub mod telemetry_service;
+pub mod weapon_service;
 
+pub use kill_service::KillService;
+pub use landing_service::LandingService;
+pub use match_service::MatchService;
 pub use player_service::PlayerService;
 pub use pubg_api_service::PubgApiService;
+pub use season_service::SeasonService;
 pub use stats_service::StatsService;
+pub use synergy_service::SynergyService;
+pub use telemetry_service::{TelemetryError, TelemetryService};
+pub use weapon_service::WeaponService;
@@ -1,32 +1,92 @@
 use chrono::Utc;
 use mongodb::bson::oid::ObjectId;
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
 use crate::{
     db::{MongoDb, PlayerRepository},
-    models::Player,
-    services::{PubgApiService, StatsService},
+    models::{Player, PubgPlayerResponse},
+    services::{KillService, MatchService, PubgApiService, StatsService},
 };
 
 pub struct PlayerService {
     pub db: Arc<MongoDb>,
     pubg_api: Arc<PubgApiService>,
+    match_service: Arc<MatchService>,
     stats_service: Arc<StatsService>,
+    kill_service: Option<Arc<KillService>>,
 }
 
 impl PlayerService {
     pub fn new(
         db: Arc<MongoDb>,
         pubg_api: Arc<PubgApiService>,
+        match_service: Arc<MatchService>,
         stats_service: Arc<StatsService>,
     ) -> Self {
         PlayerService {
             db,
             pubg_api,
+            match_service,
             stats_service,
+            kill_service: None,
         }
     }
 
+    /// Records the kills of the player's matches in the background once they are stored,
+    /// so that rivals and the bot split are read from the database.
+    pub fn with_kill_service(mut self, kill_service: Arc<KillService>) -> Self {
+        self.kill_service = Some(kill_service);
+        self
+    }
+
+    // Stats are recomputed once new kills are recorded, their bot split depends on them
+    fn record_kills_in_background(&self, player: &Player) {
+        if let Some(record_kills) = self.record_kills(player) {
+            tokio::spawn(record_kills);
+        }
+    }
+
+    fn record_kills(&self, player: &Player) -> Option<impl Future<Output = ()> + Send + 'static> {
+        let (Some(kill_service), Some(id)) = (self.kill_service.clone(), player.id) else {
+            return None;
+        };
+        let stats_service = self.stats_service.clone();
+        let account_id = player.account_id.clone();
+
+        Some(async move {
+            match kill_service.record_player(&account_id).await {
+                Ok(0) => {}
+                Ok(_) => stats_service.invalidate_cache(&id).await,
+                Err(e) => tracing::warn!("Failed to record the kills of {}: {}", account_id, e),
+            }
+        })
+    }
+
+    // A new player is returned before their matches are downloaded, the stats computed in the
+    // meantime are dropped once they are stored
+    fn store_matches_in_background(&self, player: &Player, match_ids: Vec<String>) {
+        let Some(id) = player.id else {
+            return;
+        };
+        let match_service = self.match_service.clone();
+        let stats_service = self.stats_service.clone();
+        let record_kills = self.record_kills(player);
+        let shard = player.shard.clone();
+        let name = player.name.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = match_service.store_missing(&shard, &match_ids).await {
+                tracing::warn!("Failed to store the matches of {}: {}", name, e);
+                return;
+            }
+            stats_service.invalidate_cache(&id).await;
+
+            if let Some(record_kills) = record_kills {
+                record_kills.await;
+            }
+        });
+    }
+
     #[tracing::instrument(skip(self), fields(player_name = %name, shard = %shard))]
     pub async fn add_player(
         &self,
@@ -34,14 +94,32 @@ impl PlayerService {
         shard: &str,
     ) -> Result<Player, Box<dyn std::error::Error>> {
         tracing::debug!("Starting add_player operation");
-        
-        // Check if player already exists
-        let repo = PlayerRepository::new(self.db.players());
-        
+
         // Fetch player from PUBG API
         tracing::debug!("Fetching player from PUBG API");
         let pubg_response = self.pubg_api.get_player_by_name(shard, name).await?;
-        
+        self.add_from_response(pubg_response, shard).await
+    }
+
+    /// Same as `add_player`, for a player known by their account id rather than a name that
+    /// may have changed.
+    #[tracing::instrument(skip(self), fields(account_id = %account_id, shard = %shard))]
+    pub async fn add_player_by_account_id(
+        &self,
+        account_id: &str,
+        shard: &str,
+    ) -> Result<Player, Box<dyn std::error::Error>> {
+        let pubg_response = self.pubg_api.get_player_by_id(shard, account_id).await?;
+        self.add_from_response(pubg_response, shard).await
+    }
+
+    async fn add_from_response(
+        &self,
+        pubg_response: PubgPlayerResponse,
+        shard: &str,
+    ) -> Result<Player, Box<dyn std::error::Error>> {
+        let repo = PlayerRepository::new(self.db.players());
+
         if pubg_response.data.is_empty() {
             return Err("Player not found".into());
         }
@@ -51,7 +129,7 @@ impl PlayerService {
 
         // Check if already in database
         if let Some(existing) = repo.find_by_account_id(account_id).await? {
-            tracing::info!("Player {} already exists", name);
+            tracing::info!("Player {} already exists", existing.name);
             return Ok(existing);
         }
 
@@ -73,15 +151,18 @@ impl PlayerService {
             .map(|m| m.id.clone())
             .collect();
 
-        player.last_matches = match_ids;
+        player.merge_matches(match_ids.clone());
         player.last_refreshed_at = Some(Utc::now());
 
         // Save to database
         let created_player = repo.create(player).await?;
+
+        // Store the matches while PUBG still serves them
+        self.store_matches_in_background(&created_player, match_ids);
         
         tracing::info!(
             "Player {} added successfully with ID {}",
-            name,
+            created_player.name,
             created_player.id.as_ref().unwrap().to_hex()
         );
 
@@ -98,6 +179,14 @@ impl PlayerService {
         repo.find_by_id(id).await
     }
 
+    pub async fn get_players_by_account_ids(
+        &self,
+        account_ids: &[String],
+    ) -> Result<Vec<Player>, mongodb::error::Error> {
+        let repo = PlayerRepository::new(self.db.players());
+        repo.find_by_account_ids(account_ids).await
+    }
+
     #[tracing::instrument(skip(self), fields(player_id = %id.to_hex()))]
     pub async fn refresh_player(
         &self,
@@ -124,8 +213,8 @@ impl PlayerService {
 
         let pubg_player = &pubg_response.data[0];
 
-        // Update match list (up to 100 matches to cover different time periods)
-        // These will be filtered by date when computing stats
+        // Merge the latest matches (up to 100) into the player's history, older ids are kept
+        // so periods longer than PUBG's 14 days retention can be computed
         let match_ids: Vec<String> = pubg_player
             .relationships
             .matches
@@ -136,16 +225,27 @@ impl PlayerService {
             .collect();
 
         let mut updated_player = player.clone();
-        updated_player.last_matches = match_ids;
+        updated_player.merge_matches(match_ids.clone());
         updated_player.last_refreshed_at = Some(Utc::now());
 
         // Update in database
         repo.update(id, updated_player.clone()).await?;
 
+        // Store the new matches while PUBG still serves them, the ids kept in the history
+        // would otherwise point to matches that can no longer be fetched
+        self.match_service
+            .store_missing(&player.shard, &match_ids)
+            .await?;
+        self.record_kills_in_background(&player);
+
         // Invalidate stats cache
         self.stats_service.invalidate_cache(id).await;
 
-        tracing::info!("Player {} refreshed successfully", player.name);
+        tracing::info!(
+            "Player {} refreshed successfully, {} matches in history",
+            player.name,
+            updated_player.match_history.len()
+        );
 
         Ok(updated_player)
     }
@@ -1,8 +1,17 @@
 use reqwest::{Client, header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION}};
-use std::time::Duration;
-use tokio::time::sleep;
+use std::{path::Path, time::Duration};
+use tokio::{io::AsyncWriteExt, time::sleep};
 
-use crate::models::{PubgPlayerResponse, PubgMatchResponse};
+use crate::{
+    models::{
+        PubgMatchResponse, PubgPlayerResponse, PubgPlayerSeasonResponse, PubgRankedStatsResponse,
+        PubgSeasonsResponse,
+    },
+    utils::{rate_limiter::parse_reset_header, RateLimiter},
+};
+
+// Rate limit granted to a default PUBG API key
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 10;
 
 #[derive(Debug)]
 pub enum PubgApiError {
@@ -29,14 +38,53 @@ impl std::fmt::Display for PubgApiError {
 
 impl std::error::Error for PubgApiError {}
 
+/// Failure of a telemetry download: the request itself or writing the file.
+#[derive(Debug)]
+pub enum DownloadError {
+    Api(PubgApiError),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::Api(e) => write!(f, "{}", e),
+            DownloadError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+impl From<PubgApiError> for DownloadError {
+    fn from(e: PubgApiError) -> Self {
+        DownloadError::Api(e)
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(e: std::io::Error) -> Self {
+        DownloadError::Io(e)
+    }
+}
+
 pub struct PubgApiService {
     client: Client,
     api_key: String,
     base_url: String,
+    rate_limiter: RateLimiter,
+    // The match endpoint is exempt from the rate limit for most keys
+    rate_limit_matches: bool,
 }
 
 impl PubgApiService {
     pub fn new(api_key: String, base_url: String) -> Self {
+        Self::with_rate_limit(api_key, base_url, DEFAULT_REQUESTS_PER_MINUTE)
+    }
+
+    /// Creates a service whose requests are throttled to `requests_per_minute`, the limit
+    /// attached to `api_key`.
+    pub fn with_rate_limit(api_key: String, base_url: String, requests_per_minute: u32) -> Self {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
@@ -46,9 +94,17 @@ impl PubgApiService {
             client,
             api_key,
             base_url,
+            rate_limiter: RateLimiter::new(requests_per_minute),
+            rate_limit_matches: false,
         }
     }
 
+    /// Counts match requests against the rate limit, for keys where PUBG limits them too.
+    pub fn with_rate_limited_matches(mut self, rate_limit_matches: bool) -> Self {
+        self.rate_limit_matches = rate_limit_matches;
+        self
+    }
+
     fn create_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -75,7 +131,22 @@ impl PubgApiService {
             self.base_url, shard, player_name
         );
 
-        self.make_request_with_retry(&url, 3).await
+        self.make_request_with_retry(&url, 3, true).await
+    }
+
+    #[tracing::instrument(skip(self), fields(shard = %shard, account_id = %account_id))]
+    pub async fn get_player_by_id(
+        &self,
+        shard: &str,
+        account_id: &str,
+    ) -> Result<PubgPlayerResponse, PubgApiError> {
+        tracing::debug!("Requesting player data from PUBG API");
+        let url = format!(
+            "{}/{}/players?filter[playerIds]={}",
+            self.base_url, shard, account_id
+        );
+
+        self.make_request_with_retry(&url, 3, true).await
     }
 
     pub async fn get_match(
@@ -83,15 +154,118 @@ impl PubgApiService {
         shard: &str,
         match_id: &str,
     ) -> Result<PubgMatchResponse, PubgApiError> {
+        let raw = self.get_match_raw(shard, match_id).await?;
+
+        serde_json::from_value(raw).map_err(|e| {
+            PubgApiError::ServerError(format!("Failed to parse response: {}", e))
+        })
+    }
+
+    /// Fetches a match as the untyped JSON document returned by the API. Unless the service
+    /// was told otherwise, match requests don't wait for the rate limiter.
+    pub async fn get_match_raw(
+        &self,
+        shard: &str,
+        match_id: &str,
+    ) -> Result<serde_json::Value, PubgApiError> {
         let url = format!("{}/{}/matches/{}", self.base_url, shard, match_id);
 
-        self.make_request_with_retry(&url, 3).await
+        self.make_request_with_retry(&url, 3, self.rate_limit_matches).await
+    }
+
+    /// Lists the seasons of the shard. PUBG asks not to query this more than once a month.
+    pub async fn get_seasons(&self, shard: &str) -> Result<PubgSeasonsResponse, PubgApiError> {
+        let url = format!("{}/{}/seasons", self.base_url, shard);
+
+        self.make_request_with_retry(&url, 3, true).await
+    }
+
+    #[tracing::instrument(skip(self), fields(shard = %shard, account_id = %account_id, season_id = %season_id))]
+    pub async fn get_player_season_stats(
+        &self,
+        shard: &str,
+        account_id: &str,
+        season_id: &str,
+    ) -> Result<PubgPlayerSeasonResponse, PubgApiError> {
+        let url = format!(
+            "{}/{}/players/{}/seasons/{}",
+            self.base_url, shard, account_id, season_id
+        );
+
+        self.make_request_with_retry(&url, 3, true).await
+    }
+
+    #[tracing::instrument(skip(self), fields(shard = %shard, account_id = %account_id))]
+    pub async fn get_player_lifetime_stats(
+        &self,
+        shard: &str,
+        account_id: &str,
+    ) -> Result<PubgPlayerSeasonResponse, PubgApiError> {
+        let url = format!("{}/{}/players/{}/seasons/lifetime", self.base_url, shard, account_id);
+
+        self.make_request_with_retry(&url, 3, true).await
+    }
+
+    #[tracing::instrument(skip(self), fields(shard = %shard, account_id = %account_id, season_id = %season_id))]
+    pub async fn get_player_ranked_stats(
+        &self,
+        shard: &str,
+        account_id: &str,
+        season_id: &str,
+    ) -> Result<PubgRankedStatsResponse, PubgApiError> {
+        let url = format!(
+            "{}/{}/players/{}/seasons/{}/ranked",
+            self.base_url, shard, account_id, season_id
+        );
+
+        self.make_request_with_retry(&url, 3, true).await
+    }
+
+    /// Downloads a telemetry file to `dest`, as served (PUBG serves it gzip-compressed).
+    /// Telemetry is on a CDN: no API key is sent and the rate limit does not apply.
+    pub async fn download_telemetry(&self, url: &str, dest: &Path) -> Result<u64, DownloadError> {
+        let mut response = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT_ENCODING, "gzip")
+            // 20MB+ files, the client-wide timeout is too short
+            .timeout(Duration::from_secs(300))
+            .send()
+            .await
+            .map_err(|e| PubgApiError::NetworkError(e.to_string()))?;
+
+        match response.status().as_u16() {
+            200 => {}
+            404 => return Err(PubgApiError::NotFound(url.to_string()).into()),
+            status => {
+                return Err(PubgApiError::ServerError(format!(
+                    "Unexpected status {} for telemetry",
+                    status
+                ))
+                .into())
+            }
+        }
+
+        let mut file = tokio::fs::File::create(dest).await?;
+        let mut size = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| PubgApiError::NetworkError(e.to_string()))?
+        {
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        Ok(size)
     }
 
     async fn make_request_with_retry<T>(
         &self,
         url: &str,
         max_retries: u32,
+        rate_limited: bool,
     ) -> Result<T, PubgApiError>
     where
         T: serde::de::DeserializeOwned,
@@ -100,6 +274,10 @@ impl PubgApiService {
         let mut backoff = Duration::from_secs(1);
 
         loop {
+            if rate_limited {
+                self.rate_limiter.acquire().await;
+            }
+
             let response = match self.client.get(url).headers(self.create_headers()).send().await {
                 Ok(resp) => resp,
                 Err(e) => {
@@ -116,11 +294,19 @@ impl PubgApiService {
 
             let status = response.status();
 
-            // Check rate limit headers
-            if let Some(rate_limit_reset) = response.headers().get("X-RateLimit-Reset") {
-                if let Ok(reset_str) = rate_limit_reset.to_str() {
-                    tracing::debug!("Rate limit will reset at: {}", reset_str);
-                }
+            // Resync the limiter with the server's view of our budget
+            let remaining = response
+                .headers()
+                .get("X-RateLimit-Remaining")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.parse::<u32>().ok());
+            let reset_in = response
+                .headers()
+                .get("X-RateLimit-Reset")
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_reset_header);
+            if rate_limited {
+                self.rate_limiter.sync(remaining, reset_in);
             }
 
             match status.as_u16() {
@@ -138,23 +324,25 @@ impl PubgApiService {
                     return Err(PubgApiError::Unauthorized);
                 }
                 429 => {
-                    // Rate limit exceeded
-                    let retry_after = response
-                        .headers()
-                        .get("X-RateLimit-Reset")
-                        .and_then(|v| v.to_str().ok())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .unwrap_or(60);
+                    // Rate limit exceeded, hold every caller until the window resets
+                    let retry_after = reset_in.unwrap_or(Duration::from_secs(60));
+                    if rate_limited {
+                        self.rate_limiter.block_for(retry_after);
+                    }
 
                     if retries >= max_retries {
-                        return Err(PubgApiError::RateLimit { retry_after });
+                        return Err(PubgApiError::RateLimit {
+                            retry_after: retry_after.as_secs(),
+                        });
                     }
 
                     tracing::warn!(
                         "Rate limit exceeded, waiting {} seconds before retry",
-                        retry_after
+                        retry_after.as_secs()
                     );
-                    sleep(Duration::from_secs(retry_after)).await;
+                    if !rate_limited {
+                        sleep(retry_after).await;
+                    }
                     retries += 1;
                     continue;
                 }
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use moka::future::Cache;
+use mongodb::bson;
+use std::sync::Arc;
+
+use crate::{
+    db::{MongoDb, RankedStatsRepository, SeasonStatsRepository},
+    models::{Player, PubgSeason, RankedStatsRecord, SeasonStatsRecord, LIFETIME_SEASON},
+    services::{pubg_api_service::PubgApiError, PubgApiService},
+};
+
+// Stats that can still change (current season, lifetime) are fetched again after this delay
+const LIVE_STATS_TTL_HOURS: i64 = 3;
+
+pub struct SeasonService {
+    db: Arc<MongoDb>,
+    pubg_api: Arc<PubgApiService>,
+    // Seasons of each shard, they change a few times a year
+    seasons: Cache<String, Vec<PubgSeason>>,
+}
+
+impl SeasonService {
+    pub fn new(db: Arc<MongoDb>, pubg_api: Arc<PubgApiService>) -> Self {
+        let seasons = Cache::builder()
+            .max_capacity(16)
+            .time_to_live(std::time::Duration::from_secs(24 * 3600))
+            .build();
+
+        SeasonService {
+            db,
+            pubg_api,
+            seasons,
+        }
+    }
+
+    pub async fn get_seasons(&self, shard: &str) -> Result<Vec<PubgSeason>, PubgApiError> {
+        if let Some(seasons) = self.seasons.get(shard).await {
+            return Ok(seasons);
+        }
+
+        let seasons = self.pubg_api.get_seasons(shard).await?.data;
+        self.seasons.insert(shard.to_string(), seasons.clone()).await;
+        Ok(seasons)
+    }
+
+    /// Finds a season of the shard by id, "current" standing for the current season.
+    pub async fn find_season(
+        &self,
+        shard: &str,
+        season: &str,
+    ) -> Result<Option<PubgSeason>, PubgApiError> {
+        let seasons = self.get_seasons(shard).await?;
+
+        Ok(seasons.into_iter().find(|s| {
+            if season == "current" {
+                s.attributes.is_current_season
+            } else {
+                s.id == season
+            }
+        }))
+    }
+
+    /// Official stats of the player for a season, `season` being a season id or "current".
+    /// Returns `None` for a season the shard does not know.
+    #[tracing::instrument(skip(self, player), fields(player_name = %player.name, season = %season))]
+    pub async fn get_season_stats(
+        &self,
+        player: &Player,
+        season: &str,
+    ) -> Result<Option<SeasonStatsRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        match self.find_season(&player.shard, season).await? {
+            Some(found) => {
+                let record = self
+                    .get_or_fetch(player, &found.id, found.attributes.is_current_season)
+                    .await?;
+                Ok(Some(record))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Ranked stats of the player for a season, `season` being a season id or "current".
+    /// Returns `None` for a season the shard does not know.
+    #[tracing::instrument(skip(self, player), fields(player_name = %player.name, season = %season))]
+    pub async fn get_ranked_stats(
+        &self,
+        player: &Player,
+        season: &str,
+    ) -> Result<Option<RankedStatsRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(found) = self.find_season(&player.shard, season).await? else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        let repo = RankedStatsRepository::new(self.db.ranked_stats());
+
+        let stored = repo.find(&player.account_id, &player.shard, &found.id).await?;
+        if let Some(record) = &stored
+            && record.is_fresh(now)
+        {
+            tracing::debug!("Ranked stats found in database cache");
+            return Ok(stored);
+        }
+
+        let response = match self
+            .pubg_api
+            .get_player_ranked_stats(&player.shard, &player.account_id, &found.id)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if stored.is_some() => {
+                tracing::warn!("Failed to refresh ranked stats, serving stored ones: {}", e);
+                return Ok(stored);
+            }
+            Err(e) => return Err(Box::new(e)),
+        };
+
+        let record = RankedStatsRecord {
+            id: None,
+            account_id: player.account_id.clone(),
+            shard: player.shard.clone(),
+            season_id: found.id.clone(),
+            is_current_season: found.attributes.is_current_season,
+            ranked_game_mode_stats: response.data.attributes.ranked_game_mode_stats,
+            fetched_at: now,
+            expires_at: expires_at(found.attributes.is_current_season, now),
+        };
+
+        if let Err(e) = repo.save(&record).await {
+            tracing::error!("Failed to save ranked stats to database: {}", e);
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Official lifetime stats of the player.
+    #[tracing::instrument(skip(self, player), fields(player_name = %player.name))]
+    pub async fn get_lifetime_stats(
+        &self,
+        player: &Player,
+    ) -> Result<SeasonStatsRecord, Box<dyn std::error::Error + Send + Sync>> {
+        self.get_or_fetch(player, LIFETIME_SEASON, false).await
+    }
+
+    async fn get_or_fetch(
+        &self,
+        player: &Player,
+        season_id: &str,
+        is_current_season: bool,
+    ) -> Result<SeasonStatsRecord, Box<dyn std::error::Error + Send + Sync>> {
+        let now = Utc::now();
+        let repo = SeasonStatsRepository::new(self.db.season_stats());
+
+        let stored = repo.find(&player.account_id, &player.shard, season_id).await?;
+        if let Some(record) = &stored
+            && record.is_fresh(now)
+        {
+            tracing::debug!("Season stats found in database cache");
+            return Ok(record.clone());
+        }
+
+        let response = if season_id == LIFETIME_SEASON {
+            self.pubg_api
+                .get_player_lifetime_stats(&player.shard, &player.account_id)
+                .await
+        } else {
+            self.pubg_api
+                .get_player_season_stats(&player.shard, &player.account_id, season_id)
+                .await
+        };
+
+        let response = match (response, stored) {
+            (Ok(response), _) => response,
+            (Err(e), Some(stale)) => {
+                // Outdated stats are better than none
+                tracing::warn!("Failed to refresh season stats, serving stored ones: {}", e);
+                return Ok(stale);
+            }
+            (Err(e), None) => return Err(Box::new(e)),
+        };
+
+        let record = SeasonStatsRecord {
+            id: None,
+            account_id: player.account_id.clone(),
+            shard: player.shard.clone(),
+            season_id: season_id.to_string(),
+            is_current_season,
+            game_mode_stats: response.data.attributes.game_mode_stats,
+            fetched_at: now,
+            expires_at: expires_at(season_id == LIFETIME_SEASON || is_current_season, now),
+        };
+
+        if let Err(e) = repo.save(&record).await {
+            tracing::error!("Failed to save season stats to database: {}", e);
+        }
+
+        Ok(record)
+    }
+}
+
+// Stats that can still change expire, the ones of past seasons are kept forever
+fn expires_at(live: bool, now: DateTime<Utc>) -> Option<bson::DateTime> {
+    live.then(|| bson::DateTime::from_chrono(now + chrono::Duration::hours(LIVE_STATS_TTL_HOURS)))
+}
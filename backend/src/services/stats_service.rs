@@ -1,62 +1,237 @@
-use chrono::{DateTime, Duration, Utc};
-use moka::future::Cache;
+use chrono::Utc;
 use mongodb::bson::oid::ObjectId;
-use std::sync::Arc;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing::Instrument;
 
 use crate::{
     db::{MongoDb, StatsRepository, PlayerRepository},
-    models::{PlayerStats, PubgMatchResponse},
-    services::PubgApiService,
+    models::{
+        stats::{in_scope, is_bot, BotEncounters, LobbyBreakdown, LobbyPerformance},
+        ModeFilter, PlayerStats, PubgMatchResponse, StatsPeriod,
+    },
+    services::{match_service::MATCH_RETENTION_DAYS, KillService, MatchService},
+    utils::TaggedCache,
 };
 
+// How long after `expires_at` stats are still served while being recomputed
+pub const DEFAULT_MAX_STALENESS_HOURS: i64 = 24;
+
+/// Counters of the stats cache since the server started.
+#[derive(Debug, Default)]
+pub struct StatsMetrics {
+    computations: AtomicU64,
+    deduplicated: AtomicU64, // requests that waited for a computation already running
+    stale_served: AtomicU64, // expired stats served while a refresh runs in the background
+    persisted: AtomicU64,
+    persistence_failures: AtomicU64, // computed stats that could not be saved to MongoDB
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsMetricsSnapshot {
+    pub computations: u64,
+    pub deduplicated: u64,
+    pub stale_served: u64,
+    pub persisted: u64,
+    pub persistence_failures: u64,
+}
+
+impl StatsMetrics {
+    fn record_persisted(&self, success: bool) {
+        let counter = if success {
+            &self.persisted
+        } else {
+            &self.persistence_failures
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StatsMetricsSnapshot {
+        StatsMetricsSnapshot {
+            computations: self.computations.load(Ordering::Relaxed),
+            deduplicated: self.deduplicated.load(Ordering::Relaxed),
+            stale_served: self.stale_served.load(Ordering::Relaxed),
+            persisted: self.persisted.load(Ordering::Relaxed),
+            persistence_failures: self.persistence_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Cheap to clone, background refreshes run on a clone
+#[derive(Clone)]
 pub struct StatsService {
-    pub cache: Cache<String, PlayerStats>,
+    pub cache: TaggedCache<PlayerStats>, // tagged by player, shard, mode and period
+    pub metrics: Arc<StatsMetrics>,
     pub db: Arc<MongoDb>,
-    pubg_api: Arc<PubgApiService>,
+    match_service: Arc<MatchService>,
+    // Reads bot kills from the telemetry, without it every kill counts as a human one
+    kill_service: Option<Arc<KillService>>,
+    max_staleness: chrono::Duration,
+    refreshing: Arc<Mutex<HashSet<String>>>, // cache keys being recomputed in the background
 }
 
 impl StatsService {
-    pub fn new(db: Arc<MongoDb>, pubg_api: Arc<PubgApiService>) -> Self {
+    pub fn new(db: Arc<MongoDb>, match_service: Arc<MatchService>) -> Self {
         // LRU cache with 1000 entries, TTL of 1 hour
-        let cache = Cache::builder()
-            .max_capacity(1000)
-            .time_to_live(std::time::Duration::from_secs(3600))
-            .build();
+        let cache = TaggedCache::new(1000, std::time::Duration::from_secs(3600));
+
+        StatsService {
+            cache,
+            metrics: Arc::new(StatsMetrics::default()),
+            db,
+            match_service,
+            kill_service: None,
+            max_staleness: chrono::Duration::hours(DEFAULT_MAX_STALENESS_HOURS),
+            refreshing: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Sets how long expired stats are still served, flagged stale, while they are recomputed
+    /// in the background. Past it callers wait for the recomputation.
+    pub fn with_max_staleness(mut self, max_staleness: chrono::Duration) -> Self {
+        self.max_staleness = max_staleness;
+        self
+    }
 
-        StatsService { cache, db, pubg_api }
+    pub fn with_kill_service(mut self, kill_service: Arc<KillService>) -> Self {
+        self.kill_service = Some(kill_service);
+        self
     }
 
-    #[tracing::instrument(skip(self), fields(player_id = %player_id.to_hex(), period = %period, mode = %mode, shard = %shard))]
+    #[tracing::instrument(skip(self, period, mode), fields(player_id = %player_id.to_hex(), period = %period.label(), mode = %mode.label(), shard = %shard))]
     pub async fn get_or_compute_stats(
         &self,
         player_id: &ObjectId,
-        period: &str,
-        mode: &str,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
         shard: &str,
     ) -> Result<PlayerStats, mongodb::error::Error> {
-        let cache_key = format!("{}:{}:{}:{}", player_id.to_hex(), period, mode, shard);
+        let period_label = period.label();
+        let mode_label = mode.label();
+        let cache_key = cache_key(player_id, &period_label, &mode_label, shard);
 
         // Check memory cache
         if let Some(cached_stats) = self.cache.get(&cache_key).await {
-            tracing::debug!("Stats found in memory cache");
-            return Ok(cached_stats);
+            if self.is_servable(&cached_stats) {
+                tracing::debug!("Stats found in memory cache");
+                return Ok(self.revalidate_if_expired(cached_stats, period, mode, &cache_key));
+            }
+            self.cache.invalidate(&cache_key).await;
+        }
+
+        // Concurrent requests for the same key wait for the first one instead of downloading
+        // the same matches again
+        let mut computed = false;
+        let result = self
+            .cache
+            .try_get_with(
+                cache_key.clone(),
+                cache_tags(player_id, &period_label, &mode_label, shard),
+                async {
+                    computed = true;
+                    self.load_or_compute(player_id, period, mode, shard, &cache_key).await
+                },
+            )
+            .await;
+
+        if !computed {
+            self.metrics.deduplicated.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("Stats computation for {} already in progress, reused", cache_key);
+        }
+
+        let stats = result.map_err(Arc::unwrap_or_clone)?;
+        Ok(self.revalidate_if_expired(stats, period, mode, &cache_key))
+    }
+
+    // Whether the stats can be returned, right away if fresh or flagged stale if expired
+    fn is_servable(&self, stats: &PlayerStats) -> bool {
+        Utc::now() - stats.expires_at < self.max_staleness
+    }
+
+    // Expired stats are flagged stale and recomputed in the background, once per key
+    fn revalidate_if_expired(
+        &self,
+        mut stats: PlayerStats,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+        cache_key: &str,
+    ) -> PlayerStats {
+        if stats.expires_at > Utc::now() {
+            return stats;
         }
 
+        stats.stale = true;
+        self.metrics.stale_served.fetch_add(1, Ordering::Relaxed);
+
+        if !self.refreshing.lock().unwrap().insert(cache_key.to_string()) {
+            return stats;
+        }
+
+        let service = self.clone();
+        let (player_id, shard) = (stats.player_id, stats.shard.clone());
+        let (period, mode, cache_key) = (period.clone(), mode.clone(), cache_key.to_string());
+        let span = tracing::info_span!("revalidate_stats", cache_key = %cache_key);
+        tokio::spawn(
+            async move {
+                match service.compute(&player_id, &period, &mode, &shard).await {
+                    Ok(fresh) => {
+                        let tags = cache_tags(&player_id, &fresh.period, &fresh.mode, &shard);
+                        service.cache.insert(cache_key.clone(), fresh, tags).await
+                    }
+                    Err(e) => tracing::warn!("Failed to refresh stale stats: {}", e),
+                }
+                service.refreshing.lock().unwrap().remove(&cache_key);
+            }
+            .instrument(span),
+        );
+
+        stats
+    }
+
+    // Database cache, then a full computation. The caller inserts the result in memory and
+    // refreshes expired stats.
+    async fn load_or_compute(
+        &self,
+        player_id: &ObjectId,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+        shard: &str,
+        cache_key: &str,
+    ) -> Result<PlayerStats, mongodb::error::Error> {
+        let period_label = period.label();
+        let mode_label = mode.label();
+
         // Check database cache
         let repo = StatsRepository::new(self.db.stats());
-        if let Some(db_stats) = repo.find_by_player(player_id, period, mode, shard).await? {
-            // Check if not expired
-            if db_stats.expires_at > Utc::now() {
-                tracing::debug!("Stats found in database cache for {}", cache_key);
-                self.cache.insert(cache_key, db_stats.clone()).await;
-                return Ok(db_stats);
-            }
+        if let Some(db_stats) = repo.find_by_player(player_id, &period_label, &mode_label, shard).await?
+            && self.is_servable(&db_stats)
+        {
+            tracing::debug!("Stats found in database cache for {}", cache_key);
+            return Ok(db_stats);
         }
 
-        // Stats not in cache or expired, need to compute from real PUBG API data
+        self.compute(player_id, period, mode, shard).await
+    }
+
+    // Computes the stats from the player's matches and saves them
+    async fn compute(
+        &self,
+        player_id: &ObjectId,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+        shard: &str,
+    ) -> Result<PlayerStats, mongodb::error::Error> {
+        // Stats not in cache or too old, need to compute from real PUBG API data
         tracing::info!("Computing stats from PUBG API for player {} (not in cache)", player_id.to_hex());
+        self.metrics.computations.fetch_add(1, Ordering::Relaxed);
         
-        // Fetch player to get match_ids and account_id
+        // Fetch player to get match history and account_id
         let player_repo = PlayerRepository::new(self.db.players());
         let player = player_repo
             .find_by_id(player_id)
@@ -65,25 +240,18 @@ impl StatsService {
                 mongodb::error::Error::custom(format!("Player not found: {}", player_id.to_hex()))
             })?;
 
-        // Fetch match details from PUBG API
-        let mut matches: Vec<PubgMatchResponse> = Vec::new();
-        
-        if !player.last_matches.is_empty() {
-            tracing::info!("Fetching {} matches from PUBG API", player.last_matches.len());
-            
-            for match_id in &player.last_matches {
-                match self.pubg_api.get_match(&player.shard, match_id).await {
-                    Ok(match_data) => {
-                        tracing::debug!("Successfully fetched match {}", match_id);
-                        matches.push(match_data);
-                    }
-                    Err(e) => {
-                        // Log the error but continue with other matches
-                        tracing::warn!("Failed to fetch match {}: {}", match_id, e);
-                    }
-                }
-            }
+        // The latest matches PUBG lists may not be stored yet, older ones were stored when they
+        // were listed. None of them can be in a period that ended before PUBG's retention.
+        let (_, period_end) = period.bounds(Utc::now());
+        if period_end > Utc::now() - chrono::Duration::days(MATCH_RETENTION_DAYS) {
+            self.match_service
+                .store_missing(&player.shard, &player.last_matches)
+                .await?;
         }
+        let matches = self
+            .match_service
+            .get_matches_in_scope(&[&player.account_id], period, mode)
+            .await?;
 
         // If no matches were fetched, compute stats with empty matches (will return zeros)
         if matches.is_empty() {
@@ -92,169 +260,166 @@ impl StatsService {
             tracing::info!("Computing stats from {} matches", matches.len());
         }
 
+        let encounters = self
+            .bot_encounters(&player.account_id, &matches, period, mode)
+            .await;
+
         // Compute stats from fetched matches (or empty array for zero stats)
-        let mut stats = self.compute_stats_from_matches(&player.account_id, &matches, period);
+        let mut stats = self.compute_stats_from_matches(
+            &player.account_id,
+            &matches,
+            &encounters,
+            period,
+            mode,
+        );
         
-        // Set the correct player_id, mode, and shard
+        // Set the correct player_id and shard
         stats.player_id = *player_id;
-        stats.mode = mode.to_string();
         stats.shard = shard.to_string();
 
-        // Cache the stats
-        self.cache.insert(cache_key.clone(), stats.clone()).await;
-        
-        // Save to database (async, don't wait)
+        // Save to database (async, don't wait), failures are counted in the metrics
         let db = self.db.clone();
+        let metrics = self.metrics.clone();
         let stats_to_save = stats.clone();
-        tokio::spawn(async move {
-            let repo = StatsRepository::new(db.stats());
-            if let Err(e) = repo.upsert(stats_to_save).await {
-                tracing::error!("Failed to save stats to database: {}", e);
+        tokio::spawn(
+            async move {
+                let repo = StatsRepository::new(db.stats());
+                match repo.upsert(stats_to_save).await {
+                    Ok(()) => metrics.record_persisted(true),
+                    Err(e) => {
+                        metrics.record_persisted(false);
+                        tracing::error!("Failed to save stats to database: {}", e);
+                    }
+                }
             }
-        });
+            .in_current_span(),
+        );
 
         tracing::info!("Stats computed successfully for player {}", player_id.to_hex());
         Ok(stats)
     }
 
+    // Bot kills and deaths to bots, only needed for the matches of the scope with bots
+    async fn bot_encounters(
+        &self,
+        account_id: &str,
+        matches: &[PubgMatchResponse],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> HashMap<String, BotEncounters> {
+        let Some(kill_service) = &self.kill_service else {
+            return HashMap::new();
+        };
+
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let match_ids: Vec<String> = matches
+            .iter()
+            .filter(|m| in_scope(m, mode, period_start, period_end))
+            .filter(|m| m.participants().any(|p| is_bot(&p.player_id)))
+            .map(|m| m.data.id.clone())
+            .collect();
+        if match_ids.is_empty() {
+            return HashMap::new();
+        }
+
+        match kill_service.get_bot_encounters(account_id, &match_ids).await {
+            Ok(encounters) => encounters,
+            Err(e) => {
+                tracing::warn!("Failed to read bot encounters, no match counts as covered: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
     pub fn compute_stats_from_matches(
         &self,
         player_account_id: &str,
         matches: &[PubgMatchResponse],
-        period: &str,
+        encounters: &HashMap<String, BotEncounters>,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
     ) -> PlayerStats {
         let now = Utc::now();
-        let period_start = match period {
-            "7d" => now - Duration::days(7),
-            "30d" => now - Duration::days(30),
-            "90d" => now - Duration::days(90),
-            _ => now - Duration::days(7),
-        };
+        let period_label = period.label();
+        let (period_start, period_end) = period.bounds(now);
 
         tracing::debug!(
             "Computing stats for period {} (from {} to {}), processing {} matches",
-            period,
+            period_label,
             period_start,
-            now,
+            period_end,
             matches.len()
         );
 
-        let mut total_kills = 0;
-        let mut total_deaths = 0;
-        let mut total_damage = 0.0;
-        let mut total_survival_time = 0.0;
-        let mut top1_count = 0;
-        let mut matches_in_period = 0;
+        // player_id and shard will be set by caller
+        let mut stats = PlayerStats::new(
+            ObjectId::new(),
+            period_label.clone(),
+            mode.label(),
+            "steam".to_string(),
+            0,
+        );
 
         for match_data in matches {
-            // Parse match date
-            if let Ok(match_date) = DateTime::parse_from_rfc3339(&match_data.data.attributes.created_at) {
-                let match_utc = match_date.with_timezone(&Utc);
-                
-                if match_utc < period_start {
-                    tracing::debug!("Skipping match {} from {} (before period start)", match_data.data.id, match_utc);
-                    continue; // Skip matches outside period
-                }
-                
-                tracing::debug!("Including match {} from {} (within period)", match_data.data.id, match_utc);
+            if !in_scope(match_data, mode, period_start, period_end) {
+                tracing::debug!("Skipping match {} (outside mode or period)", match_data.data.id);
+                continue;
             }
 
             // Find participant data for this player
-            for included in &match_data.included {
-                if let crate::models::PubgMatchIncluded::Participant { id: _, attributes } = included {
-                    if attributes.stats.player_id == player_account_id {
-                        matches_in_period += 1;
-                        total_kills += attributes.stats.kills;
-                        total_damage += attributes.stats.damage_dealt;
-                        total_survival_time += attributes.stats.time_survived;
-                        
-                        // Check if won (win_place = 1)
-                        if attributes.stats.win_place == 1 {
-                            top1_count += 1;
-                        }
-                        
-                        // Check if died (death_type != "alive")
-                        if attributes.stats.death_type != "alive" {
-                            total_deaths += 1;
-                        }
-                        
-                        break;
-                    }
+            if let Some(participant) = match_data.participant_stats(player_account_id) {
+                stats.add_match(participant);
+                stats.add_lobby(
+                    participant,
+                    &LobbyBreakdown::new(
+                        match_data,
+                        player_account_id,
+                        encounters.get(&match_data.data.id),
+                    ),
+                );
+                if let Some(lobby) = LobbyPerformance::new(match_data, player_account_id) {
+                    stats.add_performance(&lobby);
                 }
             }
         }
 
-        let kd_ratio = if total_deaths > 0 {
-            total_kills as f64 / total_deaths as f64
-        } else {
-            total_kills as f64
-        };
-
-        let win_rate = if matches_in_period > 0 {
-            (top1_count as f64 / matches_in_period as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        let ttl_hours = match period {
-            "7d" => 24,
-            "30d" => 72,
-            "90d" => 168,
-            _ => 24,
-        };
+        stats.compute_rates();
 
         tracing::info!(
             "Stats computed for period {}: {} matches, {} kills, {} deaths, K/D: {:.2}, Win rate: {:.1}%",
-            period,
-            matches_in_period,
-            total_kills,
-            total_deaths,
-            kd_ratio,
-            win_rate
+            period_label,
+            stats.matches_played,
+            stats.kills,
+            stats.deaths,
+            stats.kd_ratio,
+            stats.win_rate
         );
 
-        if matches_in_period == 0 {
+        if stats.matches_played == 0 {
             tracing::warn!(
                 "No matches found in period {} (checked {} total matches). Player may not have played during this period.",
-                period,
+                period_label,
                 matches.len()
             );
         }
 
-        PlayerStats {
-            id: None,
-            player_id: ObjectId::new(),  // Will be set by caller
-            period: period.to_string(),
-            mode: "all".to_string(),     // Will be set by caller
-            shard: "steam".to_string(),  // Will be set by caller
-            kills: total_kills,
-            deaths: total_deaths,
-            kd_ratio,
-            win_rate,
-            damage_dealt: total_damage,
-            survival_time: total_survival_time,
-            top1_count,
-            matches_played: matches_in_period,
-            computed_at: now,
-            expires_at: now + Duration::hours(ttl_hours),
-        }
+        stats.computed_at = now;
+        stats.expires_at = now + period.ttl(now);
+        stats
     }
 
     pub async fn save_stats(&self, stats: &PlayerStats) -> Result<(), mongodb::error::Error> {
-        let cache_key = format!(
-            "{}:{}:{}:{}",
-            stats.player_id.to_hex(),
-            stats.period,
-            stats.mode,
-            stats.shard
-        );
+        let cache_key = cache_key(&stats.player_id, &stats.period, &stats.mode, &stats.shard);
+        let tags = cache_tags(&stats.player_id, &stats.period, &stats.mode, &stats.shard);
 
         // Save to database
         let repo = StatsRepository::new(self.db.stats());
-        repo.upsert(stats.clone()).await?;
+        let saved = repo.upsert(stats.clone()).await;
+        self.metrics.record_persisted(saved.is_ok());
+        saved?;
 
         // Update memory cache
-        self.cache.insert(cache_key, stats.clone()).await;
+        self.cache.insert(cache_key, stats.clone(), tags).await;
 
         tracing::info!(
             "Stats saved for player {} ({} period)",
@@ -265,20 +430,10 @@ impl StatsService {
         Ok(())
     }
 
+    /// Drops every cached entry of the player, whatever its period, mode or shard, and their
+    /// stored stats so they get recomputed.
     pub async fn invalidate_cache(&self, player_id: &ObjectId) {
-        // Invalidate memory cache entries for this player
-        let periods = ["7d", "30d", "90d"];
-        let modes = ["solo", "duo", "squad", "all"];
-        let shards = ["steam", "xbox", "psn"];
-
-        for period in periods {
-            for mode in modes {
-                for shard in shards {
-                    let cache_key = format!("{}:{}:{}:{}", player_id.to_hex(), period, mode, shard);
-                    self.cache.invalidate(&cache_key).await;
-                }
-            }
-        }
+        let dropped = self.cache.invalidate_tagged(&[player_tag(player_id)]).await;
 
         // Also delete stats from MongoDB to force recomputation
         let repo = StatsRepository::new(self.db.stats());
@@ -286,6 +441,59 @@ impl StatsService {
             tracing::warn!("Failed to delete stats from database for player {}: {}", player_id.to_hex(), e);
         }
 
-        tracing::info!("Cache and database stats invalidated for player {}", player_id.to_hex());
+        tracing::info!(
+            "Cache ({} entries) and database stats invalidated for player {}",
+            dropped,
+            player_id.to_hex()
+        );
     }
+
+    /// Drops cached and stored stats, of one player and/or one period when given, all of them
+    /// otherwise. Returns the number of stored stats deleted.
+    pub async fn clear(
+        &self,
+        player_id: Option<&ObjectId>,
+        period: Option<&str>,
+    ) -> Result<u64, mongodb::error::Error> {
+        let deleted = StatsRepository::new(self.db.stats())
+            .delete_matching(player_id, period)
+            .await?;
+
+        let mut tags = Vec::new();
+        tags.extend(player_id.map(player_tag));
+        tags.extend(period.map(period_tag));
+        if tags.is_empty() {
+            // Entries still being computed are not indexed yet, only a full clear reaches them
+            self.cache.invalidate_all();
+            tracing::info!("Stats cleared: {} stored, all cached", deleted);
+        } else {
+            let dropped = self.cache.invalidate_tagged(&tags).await;
+            tracing::info!("Stats cleared: {} stored, {} cached", deleted, dropped);
+        }
+
+        Ok(deleted)
+    }
+}
+
+fn cache_key(player_id: &ObjectId, period: &str, mode: &str, shard: &str) -> String {
+    format!("{}:{}:{}:{}", player_id.to_hex(), period, mode, shard)
+}
+
+// Tags of a cache entry: its player, alone and with the shard or the mode, and its period
+fn cache_tags(player_id: &ObjectId, period: &str, mode: &str, shard: &str) -> Vec<String> {
+    let player = player_tag(player_id);
+    vec![
+        format!("{}:shard:{}", player, shard),
+        format!("{}:mode:{}", player, mode),
+        player,
+        period_tag(period),
+    ]
+}
+
+pub fn player_tag(player_id: &ObjectId) -> String {
+    format!("player:{}", player_id.to_hex())
+}
+
+pub fn period_tag(period: &str) -> String {
+    format!("period:{}", period)
 }
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    db::{MatchRepository, MongoDb, PlayerRepository},
+    models::{ModeFilter, Player, StatsPeriod, SynergyMember, SynergyStats, Teammate},
+    services::MatchService,
+};
+
+pub struct SynergyService {
+    db: Arc<MongoDb>,
+    match_service: Arc<MatchService>,
+}
+
+impl SynergyService {
+    pub fn new(db: Arc<MongoDb>, match_service: Arc<MatchService>) -> Self {
+        SynergyService { db, match_service }
+    }
+
+    /// Computes the "played together" stats of a group of tracked players from the stored
+    /// matches of the period and mode any of them played.
+    #[tracing::instrument(skip(self, players, period, mode), fields(players = players.len(), period = %period.label(), mode = %mode.label()))]
+    pub async fn get_synergy(
+        &self,
+        players: &[Player],
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> Result<SynergyStats, mongodb::error::Error> {
+        // Matches of the period and mode any member played, the ones they played apart count too
+        let account_ids: Vec<&str> = players.iter().map(|p| p.account_id.as_str()).collect();
+        let matches = self
+            .match_service
+            .get_matches_in_scope(&account_ids, period, mode)
+            .await?;
+
+        let members: Vec<SynergyMember> = players
+            .iter()
+            .map(|player| SynergyMember {
+                player_id: player.id.map(|id| id.to_hex()).unwrap_or_default(),
+                name: player.name.clone(),
+                account_id: player.account_id.clone(),
+            })
+            .collect();
+
+        let stats = SynergyStats::compute(&members, &matches, period, mode);
+        tracing::info!(
+            "Synergy computed from {} matches: {} played together",
+            matches.len(),
+            stats.matches_together
+        );
+
+        Ok(stats)
+    }
+
+    /// Returns the player's most frequent teammates, flagging the ones already tracked.
+    #[tracing::instrument(skip(self, player, period, mode), fields(player_name = %player.name, period = %period.label(), mode = %mode.label()))]
+    pub async fn get_teammates(
+        &self,
+        player: &Player,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+        limit: usize,
+    ) -> Result<Vec<Teammate>, mongodb::error::Error> {
+        let matches = self
+            .match_service
+            .get_matches_in_scope(&[&player.account_id], period, mode)
+            .await?;
+
+        let mut teammates = Teammate::from_matches(&player.account_id, &matches, period, mode);
+        teammates.truncate(limit);
+
+        let account_ids: Vec<String> = teammates.iter().map(|t| t.account_id.clone()).collect();
+        let player_repo = PlayerRepository::new(self.db.players());
+        let tracked: HashMap<String, String> = player_repo
+            .find_by_account_ids(&account_ids)
+            .await?
+            .into_iter()
+            .filter_map(|p| p.id.map(|id| (p.account_id, id.to_hex())))
+            .collect();
+
+        for teammate in &mut teammates {
+            teammate.player_id = tracked.get(&teammate.account_id).cloned();
+            teammate.tracked = teammate.player_id.is_some();
+        }
+
+        Ok(teammates)
+    }
+
+    /// Whether `account_id` was on the player's roster in one of their stored matches.
+    pub async fn played_together(
+        &self,
+        player: &Player,
+        account_id: &str,
+    ) -> Result<bool, mongodb::error::Error> {
+        let repo = MatchRepository::new(self.db.matches());
+        let records = repo
+            .find_with_participants(&[&player.account_id, account_id])
+            .await?;
+
+        // Sharing a lobby is not enough, opponents are in the same matches
+        Ok(records.iter().any(|record| match record.to_response() {
+            Ok(match_data) => match_data
+                .team_of(&player.account_id)
+                .is_some_and(|team| team.members.iter().any(|m| m.player_id == account_id)),
+            Err(e) => {
+                tracing::warn!("Failed to decode stored match {}: {}", record.id, e);
+                false
+            }
+        }))
+    }
+}
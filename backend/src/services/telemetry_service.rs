@@ -0,0 +1,345 @@
+use serde::de::{Deserializer as _, IgnoredAny, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    models::{
+        is_valid_match_id, timeline::TimelineBuilder, tracks::TracksBuilder, PlayerTrack,
+        PubgMatchResponse, TelemetryEvent, TimelineEvent, ZoneTrack,
+    },
+    services::{
+        pubg_api_service::{DownloadError, PubgApiError},
+        MatchService, PubgApiService,
+    },
+};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Telemetry files read at the same time when aggregating several matches, each one may have
+// to be downloaded first (around 20MB)
+pub const TELEMETRY_CONCURRENCY: usize = 4;
+
+#[derive(Debug)]
+pub enum TelemetryError {
+    InvalidMatchId(String),
+    MatchNotFound(String),
+    NoTelemetry(String), // the match has no telemetry asset
+    Match(String),       // the match could not be loaded
+    Api(PubgApiError),
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl std::fmt::Display for TelemetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TelemetryError::InvalidMatchId(id) => write!(f, "Invalid match id: {}", id),
+            TelemetryError::MatchNotFound(id) => write!(f, "Match not found: {}", id),
+            TelemetryError::NoTelemetry(id) => write!(f, "No telemetry for match {}", id),
+            TelemetryError::Match(msg) => write!(f, "Failed to load match: {}", msg),
+            TelemetryError::Api(e) => write!(f, "Failed to download telemetry: {}", e),
+            TelemetryError::Io(e) => write!(f, "Telemetry file error: {}", e),
+            TelemetryError::Parse(e) => write!(f, "Invalid telemetry: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TelemetryError {}
+
+impl From<PubgApiError> for TelemetryError {
+    fn from(e: PubgApiError) -> Self {
+        TelemetryError::Api(e)
+    }
+}
+
+impl From<DownloadError> for TelemetryError {
+    fn from(e: DownloadError) -> Self {
+        match e {
+            DownloadError::Api(e) => TelemetryError::Api(e),
+            DownloadError::Io(e) => TelemetryError::Io(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for TelemetryError {
+    fn from(e: std::io::Error) -> Self {
+        TelemetryError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for TelemetryError {
+    fn from(e: serde_json::Error) -> Self {
+        TelemetryError::Parse(e)
+    }
+}
+
+/// Downloads match telemetry once, keeps it on disk and replays its events.
+pub struct TelemetryService {
+    dir: PathBuf,
+    pubg_api: Arc<PubgApiService>,
+    match_service: Arc<MatchService>,
+    // Distinguishes the temporary files of concurrent downloads
+    download_seq: AtomicU64,
+}
+
+impl TelemetryService {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        pubg_api: Arc<PubgApiService>,
+        match_service: Arc<MatchService>,
+    ) -> Self {
+        TelemetryService {
+            dir: dir.into(),
+            pubg_api,
+            match_service,
+            download_seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Location of the telemetry of a match. The file is kept as served by PUBG, gzip-compressed
+    /// or not.
+    pub fn path(&self, match_id: &str) -> Result<PathBuf, TelemetryError> {
+        if !is_valid_match_id(match_id) {
+            return Err(TelemetryError::InvalidMatchId(match_id.to_string()));
+        }
+
+        Ok(self.dir.join(format!("{}.json", match_id)))
+    }
+
+    /// Returns the path of the match telemetry, downloading it first if needed.
+    #[tracing::instrument(skip(self), fields(shard = %shard, match_id = %match_id))]
+    pub async fn ensure_downloaded(
+        &self,
+        shard: &str,
+        match_id: &str,
+    ) -> Result<PathBuf, TelemetryError> {
+        let path = self.path(match_id)?;
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+
+        let match_data = self
+            .match_service
+            .get_match(shard, match_id)
+            .await
+            .map_err(|e| TelemetryError::Match(e.to_string()))?
+            .ok_or_else(|| TelemetryError::MatchNotFound(match_id.to_string()))?;
+        let url = match_data
+            .telemetry_url()
+            .ok_or_else(|| TelemetryError::NoTelemetry(match_id.to_string()))?;
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        // Download next to the final file then rename, so a partial file is never read
+        let seq = self.download_seq.fetch_add(1, Ordering::Relaxed);
+        let part = self.dir.join(format!("{}.json.{}.part", match_id, seq));
+
+        let result = self.pubg_api.download_telemetry(url, &part).await;
+        let size = match result {
+            Ok(size) => size,
+            Err(e) => {
+                tokio::fs::remove_file(&part).await.ok();
+                return Err(e.into());
+            }
+        };
+        tokio::fs::rename(&part, &path).await?;
+
+        tracing::info!("Telemetry of match {} downloaded ({} bytes)", match_id, size);
+        Ok(path)
+    }
+
+    /// Deletes the telemetry files downloaded more than `max_age` ago, they are downloaded again
+    /// when needed. Returns how many were deleted.
+    pub async fn remove_expired(&self, max_age: std::time::Duration) -> std::io::Result<usize> {
+        remove_expired_files(&self.dir, max_age).await
+    }
+
+    /// Runs `f` over every event of the match telemetry, in order, and returns the
+    /// accumulator. The file is parsed as a stream on a blocking thread.
+    pub async fn fold<T, F>(
+        &self,
+        shard: &str,
+        match_id: &str,
+        init: T,
+        mut f: F,
+    ) -> Result<T, TelemetryError>
+    where
+        T: Send + 'static,
+        F: FnMut(&mut T, TelemetryEvent) + Send + 'static,
+    {
+        let path = self.ensure_downloaded(shard, match_id).await?;
+
+        tokio::task::spawn_blocking(move || {
+            let mut acc = init;
+            let file = std::fs::File::open(&path)?;
+            read_events(file, |event| f(&mut acc, event))?;
+            Ok(acc)
+        })
+        .await
+        .map_err(|e| TelemetryError::Io(std::io::Error::other(e)))?
+    }
+
+    /// Play-by-play of the match for the tracked players in it. `tracked` maps their account
+    /// ids to their player id.
+    #[tracing::instrument(skip(self, match_data, tracked), fields(match_id = %match_data.data.id))]
+    pub async fn get_timeline(
+        &self,
+        match_data: &PubgMatchResponse,
+        tracked: HashMap<String, String>,
+    ) -> Result<Vec<TimelineEvent>, TelemetryError> {
+        let attributes = &match_data.data.attributes;
+        let created_at = chrono::DateTime::parse_from_rfc3339(&attributes.created_at)
+            .ok()
+            .map(|date| date.with_timezone(&chrono::Utc));
+
+        let builder = self
+            .fold(
+                &attributes.shard_id,
+                &match_data.data.id,
+                TimelineBuilder::new(tracked, created_at),
+                |builder, event| builder.add_event(event),
+            )
+            .await?;
+
+        Ok(builder.finish())
+    }
+
+    /// Positions of the tracked players and zone state over the match, one sample every
+    /// `interval` seconds at most.
+    #[tracing::instrument(skip(self, match_data, tracked), fields(match_id = %match_data.data.id))]
+    pub async fn get_tracks(
+        &self,
+        match_data: &PubgMatchResponse,
+        tracked: HashMap<String, String>,
+        interval: u32,
+    ) -> Result<(Vec<PlayerTrack>, ZoneTrack), TelemetryError> {
+        let builder = self
+            .fold(
+                &match_data.data.attributes.shard_id,
+                &match_data.data.id,
+                TracksBuilder::new(tracked, interval),
+                |builder, event| builder.add_event(event),
+            )
+            .await?;
+
+        Ok(builder.finish())
+    }
+}
+
+/// Deletes the files of `dir` last modified more than `max_age` ago, telemetry files as well as
+/// downloads left unfinished. A missing directory has nothing to delete.
+pub async fn remove_expired_files(
+    dir: &Path,
+    max_age: std::time::Duration,
+) -> std::io::Result<usize> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let expired = metadata
+            .modified()?
+            .elapsed()
+            .is_ok_and(|age| age > max_age);
+        if !metadata.is_file() || !expired {
+            continue;
+        }
+
+        // Deleted by a concurrent cleanup in the meantime
+        match tokio::fs::remove_file(entry.path()).await {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Reads a telemetry document, calling `on_event` for each event without holding the whole
+/// file in memory. Gzip-compressed content is detected and decompressed on the fly. Events
+/// that do not match their model are skipped. Returns the number of events read.
+pub fn read_events<R, F>(reader: R, on_event: F) -> Result<usize, TelemetryError>
+where
+    R: Read,
+    F: FnMut(TelemetryEvent),
+{
+    let mut reader = BufReader::new(reader);
+    let compressed = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+
+    if compressed {
+        let decoder = BufReader::new(flate2::bufread::MultiGzDecoder::new(reader));
+        read_json_events(decoder, on_event)
+    } else {
+        read_json_events(reader, on_event)
+    }
+}
+
+fn read_json_events<R, F>(reader: R, on_event: F) -> Result<usize, TelemetryError>
+where
+    R: Read,
+    F: FnMut(TelemetryEvent),
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let count = deserializer.deserialize_seq(EventVisitor { on_event })?;
+    deserializer.end()?;
+    Ok(count)
+}
+
+// An event that fails to parse is kept as `Invalid` instead of failing the whole file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeEvent {
+    Event(Box<TelemetryEvent>),
+    Invalid(IgnoredAny),
+}
+
+struct EventVisitor<F> {
+    on_event: F,
+}
+
+impl<'de, F> Visitor<'de> for EventVisitor<F>
+where
+    F: FnMut(TelemetryEvent),
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of telemetry events")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<usize, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut count = 0;
+        let mut skipped = 0;
+
+        while let Some(event) = seq.next_element::<MaybeEvent>()? {
+            match event {
+                MaybeEvent::Event(event) => {
+                    (self.on_event)(*event);
+                    count += 1;
+                }
+                MaybeEvent::Invalid(_) => skipped += 1,
+            }
+        }
+
+        if skipped > 0 {
+            tracing::warn!("Skipped {} unreadable telemetry events", skipped);
+        }
+        Ok(count)
+    }
+}
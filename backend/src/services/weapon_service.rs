@@ -0,0 +1,125 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    db::{MatchRepository, MongoDb, WeaponRepository},
+    models::{
+        MatchWeapons, MatchWeaponsRecord, ModeFilter, Player, StatsPeriod, WeaponStats,
+        WeaponTotals,
+    },
+    services::{telemetry_service::TELEMETRY_CONCURRENCY, TelemetryError, TelemetryService},
+};
+
+pub struct WeaponService {
+    db: Arc<MongoDb>,
+    telemetry_service: Arc<TelemetryService>,
+}
+
+impl WeaponService {
+    pub fn new(db: Arc<MongoDb>, telemetry_service: Arc<TelemetryService>) -> Self {
+        WeaponService {
+            db,
+            telemetry_service,
+        }
+    }
+
+    /// Weapon totals of every player of a match, read from its telemetry and stored, parsing
+    /// telemetry is expensive and matches never change.
+    pub async fn record_match_weapons(
+        &self,
+        shard: &str,
+        match_id: &str,
+    ) -> Result<MatchWeaponsRecord, TelemetryError> {
+        let weapons = self
+            .telemetry_service
+            .fold(
+                shard,
+                match_id,
+                MatchWeapons::default(),
+                |weapons, event| weapons.add_event(&event),
+            )
+            .await?;
+
+        let record = MatchWeaponsRecord::new(match_id, shard, &weapons);
+        if let Err(e) = WeaponRepository::new(self.db.match_weapons()).save(&record).await {
+            tracing::warn!("Failed to store weapons of match {}: {}", match_id, e);
+        }
+        Ok(record)
+    }
+
+    /// Weapon stats of the player over the stored matches of the period and mode, with the
+    /// number of matches whose telemetry could be read. Matches whose weapon totals are not
+    /// stored yet are read from their telemetry.
+    #[tracing::instrument(skip(self, player, period, mode), fields(player_name = %player.name, period = %period.label(), mode = %mode.label()))]
+    pub async fn get_player_weapons(
+        &self,
+        player: &Player,
+        period: &StatsPeriod,
+        mode: &ModeFilter,
+    ) -> Result<(usize, Vec<WeaponStats>), TelemetryError> {
+        let (period_start, period_end) = period.bounds(Utc::now());
+        let headers = MatchRepository::new(self.db.matches())
+            .find_headers_for_player(
+                &player.account_id,
+                Some(period_start),
+                Some(period_end),
+                None,
+                mode.game_mode_pattern(),
+                false,
+            )
+            .await
+            .map_err(|e| TelemetryError::Match(e.to_string()))?;
+        let match_ids: Vec<String> = headers.iter().map(|h| h.id.clone()).collect();
+
+        let mut records = WeaponRepository::new(self.db.match_weapons())
+            .find_for_account(&match_ids, &player.account_id)
+            .await
+            .map_err(|e| TelemetryError::Match(e.to_string()))?;
+        let stored: HashSet<String> = records.iter().map(|r| r.id.clone()).collect();
+
+        // Owned ids, borrowed ones don't make a `Send` future here
+        let missing: Vec<(String, String)> = headers
+            .into_iter()
+            .filter(|h| !stored.contains(&h.id))
+            .map(|h| (h.shard, h.id))
+            .collect();
+        let read = missing.len();
+        let mut results = stream::iter(missing)
+            .map(|(shard, match_id)| async move {
+                let weapons = self.record_match_weapons(&shard, &match_id).await;
+                (match_id, weapons)
+            })
+            .buffer_unordered(TELEMETRY_CONCURRENCY);
+
+        while let Some((match_id, result)) = results.next().await {
+            match result {
+                Ok(record) => records.push(record),
+                // Log the error but continue with other matches
+                Err(e) => tracing::warn!("Skipping telemetry of match {}: {}", match_id, e),
+            }
+        }
+
+        let mut totals: HashMap<String, WeaponTotals> = HashMap::new();
+        for record in &records {
+            if let Some(weapons) = record.weapons_of(&player.account_id) {
+                for (weapon, weapon_totals) in weapons {
+                    totals
+                        .entry(weapon.clone())
+                        .or_default()
+                        .merge(weapon_totals);
+                }
+            }
+        }
+
+        tracing::info!(
+            "Weapon stats computed from {} matches, {} read from telemetry",
+            records.len(),
+            read
+        );
+        Ok((records.len(), WeaponStats::from_totals(&totals)))
+    }
+}
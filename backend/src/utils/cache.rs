@@ -1 +1,170 @@
-// Cache utilities
+use moka::future::Cache;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// In-memory cache whose entries carry tags, so that every entry sharing a tag can be dropped
+/// without knowing their keys.
+///
+/// Tags live in an index next to the moka cache. Entries leaving the cache (expiry, capacity,
+/// invalidation) are removed from the index by the eviction listener. Each stored entry gets a
+/// generation number, so the listener only drops the tags of the entry it is notified about
+/// and never those of a newer entry stored under the same key.
+#[derive(Clone)]
+pub struct TaggedCache<V> {
+    entries: Cache<String, Entry<V>>,
+    index: Arc<Mutex<TagIndex>>,
+    generation: Arc<AtomicU64>,
+}
+
+#[derive(Clone)]
+struct Entry<V> {
+    generation: u64,
+    value: V,
+}
+
+#[derive(Default)]
+struct TagIndex {
+    tags: HashMap<String, HashSet<String>>,   // tag -> keys
+    keys: HashMap<String, (u64, Vec<String>)>, // key -> generation, tags
+}
+
+impl TagIndex {
+    fn add(&mut self, key: &str, generation: u64, tags: Vec<String>) {
+        self.remove(key);
+        for tag in &tags {
+            self.tags.entry(tag.clone()).or_default().insert(key.to_string());
+        }
+        self.keys.insert(key.to_string(), (generation, tags));
+    }
+
+    // Removes the key only if it is still indexed for this generation
+    fn remove_generation(&mut self, key: &str, generation: u64) {
+        if self.keys.get(key).is_some_and(|(indexed, _)| *indexed == generation) {
+            self.remove(key);
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        let (_, tags) = self.keys.remove(key).unwrap_or_default();
+        for tag in tags {
+            if let Some(keys) = self.tags.get_mut(&tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.tags.remove(&tag);
+                }
+            }
+        }
+    }
+
+    // Keys carrying every one of `tags`
+    fn matching(&self, tags: &[String]) -> Vec<String> {
+        let Some((first, rest)) = tags.split_first() else {
+            return self.keys.keys().cloned().collect();
+        };
+
+        self.tags
+            .get(first)
+            .into_iter()
+            .flatten()
+            .filter(|key| rest.iter().all(|tag| self.keys[*key].1.contains(tag)))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<V: Clone + Send + Sync + 'static> TaggedCache<V> {
+    pub fn new(max_capacity: u64, time_to_live: Duration) -> Self {
+        let index = Arc::new(Mutex::new(TagIndex::default()));
+
+        let listener_index = index.clone();
+        let entries = Cache::builder()
+            .max_capacity(max_capacity)
+            .time_to_live(time_to_live)
+            .eviction_listener(move |key: Arc<String>, entry: Entry<V>, _| {
+                listener_index
+                    .lock()
+                    .unwrap()
+                    .remove_generation(&key, entry.generation);
+            })
+            .build();
+
+        TaggedCache {
+            entries,
+            index,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Option<V> {
+        self.entries.get(key).await.map(|entry| entry.value)
+    }
+
+    pub async fn insert(&self, key: String, value: V, tags: Vec<String>) {
+        let generation = self.next_generation();
+        self.index.lock().unwrap().add(&key, generation, tags);
+        self.entries.insert(key, Entry { generation, value }).await;
+    }
+
+    /// Returns the cached value or runs `init`. Concurrent calls for the same key wait for the
+    /// first one's `init` instead of running their own.
+    pub async fn try_get_with<F, E>(
+        &self,
+        key: String,
+        tags: Vec<String>,
+        init: F,
+    ) -> Result<V, Arc<E>>
+    where
+        F: Future<Output = Result<V, E>>,
+        E: Send + Sync + 'static,
+    {
+        let generation = self.next_generation();
+        let entry = self
+            .entries
+            .try_get_with(key.clone(), async move {
+                init.await.map(|value| Entry { generation, value })
+            })
+            .await?;
+        // Indexed once stored, a concurrent invalidation may already have dropped it
+        if entry.generation == generation && self.entries.contains_key(&key) {
+            self.index.lock().unwrap().add(&key, generation, tags);
+        }
+        Ok(entry.value)
+    }
+
+    pub async fn invalidate(&self, key: &str) {
+        self.entries.invalidate(key).await;
+    }
+
+    /// Drops the entries carrying every one of `tags`, all of them when `tags` is empty.
+    /// Returns how many entries were dropped.
+    pub async fn invalidate_tagged(&self, tags: &[String]) -> usize {
+        let keys = self.index.lock().unwrap().matching(tags);
+        for key in &keys {
+            self.entries.invalidate(key).await;
+        }
+        keys.len()
+    }
+
+    pub fn invalidate_all(&self) {
+        self.entries.invalidate_all();
+        let mut index = self.index.lock().unwrap();
+        index.tags.clear();
+        index.keys.clear();
+    }
+
+    /// Keys carrying every one of `tags`.
+    pub fn keys_tagged(&self, tags: &[String]) -> Vec<String> {
+        self.index.lock().unwrap().matching(tags)
+    }
+
+    fn next_generation(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::Relaxed)
+    }
+}
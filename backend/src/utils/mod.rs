@@ -1,3 +1,7 @@
 // Placeholder for utilities module
-pub mod retry;
 pub mod cache;
+pub mod rate_limiter;
+pub mod retry;
+
+pub use cache::TaggedCache;
+pub use rate_limiter::RateLimiter;
@@ -0,0 +1,142 @@
+use std::{
+    sync::Mutex as StdMutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{
+    sync::Mutex,
+    time::{sleep_until, Instant},
+};
+
+/// Token bucket shared by every request made with one PUBG API key.
+///
+/// Callers wait their turn on a FIFO queue, so a burst of requests is spread over the
+/// rate window in arrival order instead of each request hitting a 429 and retrying on its own.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    queue: Mutex<()>,
+    bucket: StdMutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    // Set when the API reports an exhausted window, nobody is let through before it
+    blocked_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        let capacity = requests_per_minute.max(1) as f64;
+
+        RateLimiter {
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            queue: Mutex::new(()),
+            bucket: StdMutex::new(Bucket {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent and consumes one token.
+    pub async fn acquire(&self) {
+        // Only the caller at the head of the queue competes for tokens
+        let _turn = self.queue.lock().await;
+
+        loop {
+            let wait_until = {
+                let mut bucket = self.bucket.lock().unwrap();
+                self.try_take(&mut bucket, Instant::now())
+            };
+
+            match wait_until {
+                Some(until) => sleep_until(until).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Resynchronizes the bucket with the `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+    /// headers of a response.
+    pub fn sync(&self, remaining: Option<u32>, reset_in: Option<Duration>) {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        self.refill(&mut bucket, now);
+
+        if let Some(remaining) = remaining {
+            bucket.tokens = bucket.tokens.min(remaining as f64);
+
+            if remaining == 0 {
+                let reset_in = reset_in.unwrap_or(Duration::from_secs(60));
+                bucket.blocked_until = Some(now + reset_in);
+                tracing::debug!(
+                    "Rate limit window exhausted, pausing requests for {:?}",
+                    reset_in
+                );
+            }
+        }
+    }
+
+    /// Blocks every caller for `duration`, used when the API answers with a 429.
+    pub fn block_for(&self, duration: Duration) {
+        let mut bucket = self.bucket.lock().unwrap();
+        let until = Instant::now() + duration;
+
+        bucket.tokens = 0.0;
+        bucket.blocked_until = Some(
+            bucket
+                .blocked_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+
+    // Takes a token if one is available, otherwise returns when to try again
+    fn try_take(&self, bucket: &mut Bucket, now: Instant) -> Option<Instant> {
+        if let Some(until) = bucket.blocked_until {
+            if until > now {
+                return Some(until);
+            }
+
+            // The server-side window has been reset
+            bucket.blocked_until = None;
+            bucket.tokens = self.capacity;
+            bucket.last_refill = now;
+        }
+
+        self.refill(bucket, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Some(now + Duration::from_secs_f64(missing / self.refill_per_sec))
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(bucket.last_refill)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+    }
+}
+
+/// Converts an `X-RateLimit-Reset` value into a delay.
+///
+/// PUBG sends a Unix timestamp, but small values are accepted as a number of seconds.
+pub fn parse_reset_header(value: &str) -> Option<Duration> {
+    let value = value.trim().parse::<u64>().ok()?;
+
+    if value > 1_000_000_000 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(Duration::from_secs(value.saturating_sub(now)))
+    } else {
+        Some(Duration::from_secs(value))
+    }
+}
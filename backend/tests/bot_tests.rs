@@ -0,0 +1,117 @@
+mod common;
+
+#[cfg(test)]
+mod bot_tests {
+    use crate::common::mock_match;
+    use bson::oid::ObjectId;
+    use pubg_tracker_api::models::{
+        stats::{is_bot, BotEncounters, LobbyBreakdown},
+        MatchHistoryEntry, PlayerStats, PubgMatchResponse, StatsResponse,
+    };
+
+    // Two bot teams ahead of the player, one human team ahead
+    fn bot_lobby() -> PubgMatchResponse {
+        mock_match(
+            "m1",
+            &[
+                (1, &[("ai.1", 0), ("ai.2", 1)]),
+                (2, &[("account.b", 2), ("ai.3", 0)]),
+                (3, &[("ai.4", 0)]),
+                (4, &[("account.a", 5)]),
+                (5, &[("account.c", 0)]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_is_bot() {
+        assert!(is_bot("ai.12345"));
+        assert!(!is_bot("account.abc"));
+    }
+
+    #[test]
+    fn test_lobby_breakdown() {
+        let match_data = bot_lobby();
+        let encounters = BotEncounters {
+            bot_kills: 3,
+            bot_headshot_kills: 1,
+            killed_by_bot: true,
+        };
+
+        let lobby = LobbyBreakdown::new(&match_data, "account.a", Some(&encounters));
+        assert_eq!(lobby.humans, 3);
+        assert_eq!(lobby.bots, 4);
+        assert_eq!(lobby.human_placement, 2); // only the team of account.b counts
+        assert_eq!(lobby.bot_kills, 3);
+        assert_eq!(lobby.bot_headshot_kills, 1);
+        assert!(lobby.killed_by_bot);
+        assert!(lobby.with_telemetry);
+
+        // No telemetry: every kill counts as a human one
+        let lobby = LobbyBreakdown::new(&match_data, "account.a", None);
+        assert_eq!(lobby.bot_kills, 0);
+        assert!(!lobby.killed_by_bot);
+        assert!(!lobby.with_telemetry);
+
+        // Without bots the encounters are ignored
+        let human_match = mock_match("m2", &[(1, &[("account.a", 2)]), (2, &[("account.b", 0)])]);
+        let lobby = LobbyBreakdown::new(&human_match, "account.a", Some(&encounters));
+        assert_eq!((lobby.humans, lobby.bots), (2, 0));
+        assert_eq!(lobby.human_placement, 1);
+        assert_eq!(lobby.bot_kills, 0);
+    }
+
+    #[test]
+    fn test_human_kills_split() {
+        let match_data = bot_lobby();
+        let mut participant = match_data.participant_stats("account.a").unwrap().clone();
+        participant.headshot_kills = 3;
+        let participant = &participant;
+        let encounters = BotEncounters {
+            bot_kills: 3,
+            bot_headshot_kills: 2,
+            killed_by_bot: false,
+        };
+
+        let mut stats = PlayerStats::new(
+            ObjectId::new(),
+            "7d".to_string(),
+            "all".to_string(),
+            "steam".to_string(),
+            0,
+        );
+        stats.add_match(participant);
+        stats.add_lobby(
+            participant,
+            &LobbyBreakdown::new(&match_data, "account.a", Some(&encounters)),
+        );
+        stats.compute_rates();
+
+        assert_eq!(stats.kills, 5);
+        assert_eq!(stats.bot_kills, 3);
+        assert_eq!(stats.human_kills, 2);
+        assert_eq!(stats.deaths, 1);
+        assert_eq!(stats.deaths_to_bots, 0);
+        assert_eq!(stats.human_kd_ratio, 2.0);
+        assert_eq!(stats.avg_humans_in_lobby, 3.0);
+        assert_eq!(stats.avg_bots_in_lobby, 4.0);
+        assert_eq!(stats.avg_human_placement, 2.0);
+        assert_eq!(stats.bot_headshot_kills, 2);
+        assert_eq!((stats.matches_with_bots, stats.matches_with_telemetry), (1, 1));
+
+        let response = StatsResponse::from(stats).humans_only();
+        assert!(response.humans_only);
+        assert_eq!(response.kills, 2);
+        assert_eq!(response.kd_ratio, 2.0);
+        assert_eq!(response.avg_kills, 2.0);
+        assert_eq!(response.headshot_kills, 1);
+        assert_eq!(response.headshot_rate, 50.0);
+    }
+
+    #[test]
+    fn test_match_history_lobby_counts() {
+        let entry = MatchHistoryEntry::from_match(&bot_lobby(), "account.a").unwrap();
+        assert_eq!(entry.humans_in_lobby, 3);
+        assert_eq!(entry.bots_in_lobby, 4);
+    }
+}
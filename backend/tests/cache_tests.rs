@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod cache_tests {
+    use pubg_tracker_api::utils::TaggedCache;
+    use std::time::Duration;
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    async fn filled_cache() -> TaggedCache<u32> {
+        let cache = TaggedCache::new(100, Duration::from_secs(60));
+        cache.insert("a:7d:steam".to_string(), 1, tags(&["player:a", "period:7d"])).await;
+        cache.insert("a:30d:kakao".to_string(), 2, tags(&["player:a", "period:30d"])).await;
+        cache.insert("b:7d:steam".to_string(), 3, tags(&["player:b", "period:7d"])).await;
+        cache
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_by_tag() {
+        let cache = filled_cache().await;
+
+        assert_eq!(cache.invalidate_tagged(&tags(&["player:a"])).await, 2);
+        assert!(cache.get("a:7d:steam").await.is_none());
+        assert!(cache.get("a:30d:kakao").await.is_none());
+        assert_eq!(cache.get("b:7d:steam").await, Some(3));
+        assert!(cache.keys_tagged(&tags(&["player:a"])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_by_several_tags() {
+        let cache = filled_cache().await;
+
+        // Both tags are needed
+        assert_eq!(cache.invalidate_tagged(&tags(&["player:a", "period:7d"])).await, 1);
+        assert!(cache.get("a:7d:steam").await.is_none());
+        assert_eq!(cache.get("a:30d:kakao").await, Some(2));
+        assert_eq!(cache.get("b:7d:steam").await, Some(3));
+
+        // No tag: everything
+        assert_eq!(cache.invalidate_tagged(&[]).await, 2);
+        assert!(cache.get("b:7d:steam").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_removed_entries_leave_the_index() {
+        let cache = filled_cache().await;
+
+        cache.invalidate("b:7d:steam").await;
+        assert_eq!(cache.keys_tagged(&tags(&["period:7d"])), vec!["a:7d:steam"]);
+
+        // Replacing an entry updates its tags
+        cache.insert("a:7d:steam".to_string(), 4, tags(&["player:a", "period:custom"])).await;
+        assert_eq!(cache.get("a:7d:steam").await, Some(4));
+        assert!(cache.keys_tagged(&tags(&["period:7d"])).is_empty());
+        assert_eq!(cache.keys_tagged(&tags(&["period:custom"])), vec!["a:7d:steam"]);
+    }
+
+    #[tokio::test]
+    async fn test_try_get_with_indexes_the_entry() {
+        let cache: TaggedCache<u32> = TaggedCache::new(100, Duration::from_secs(60));
+
+        let value = cache
+            .try_get_with("c:7d".to_string(), tags(&["player:c"]), async {
+                Ok::<_, std::io::Error>(7)
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(cache.keys_tagged(&tags(&["player:c"])), vec!["c:7d"]);
+
+        // Errors are not cached
+        let result = cache
+            .try_get_with("d:7d".to_string(), tags(&["player:d"]), async {
+                Err::<u32, _>(std::io::Error::other("boom"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(cache.keys_tagged(&tags(&["player:d"])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_insert_over_an_expired_key_keeps_its_tags() {
+        let cache: TaggedCache<u32> = TaggedCache::new(100, Duration::from_millis(50));
+        cache.insert("e:7d".to_string(), 1, tags(&["player:e", "period:7d"])).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.get("e:7d").await.is_none());
+
+        // The expired entry is only purged now, its removal must not drop the new tags
+        cache.insert("e:7d".to_string(), 2, tags(&["player:e", "period:30d"])).await;
+        cache.insert("f:7d".to_string(), 3, tags(&["player:f"])).await;
+        assert_eq!(cache.get("e:7d").await, Some(2));
+        assert_eq!(cache.keys_tagged(&tags(&["player:e"])), vec!["e:7d"]);
+        assert!(cache.keys_tagged(&tags(&["period:7d"])).is_empty());
+        assert_eq!(cache.keys_tagged(&tags(&["period:30d"])), vec!["e:7d"]);
+    }
+
+    #[tokio::test]
+    async fn test_insert_over_an_invalidated_key_keeps_its_tags() {
+        let cache = filled_cache().await;
+
+        cache.invalidate("a:7d:steam").await;
+        cache.insert("a:7d:steam".to_string(), 5, tags(&["player:a", "period:7d"])).await;
+        assert_eq!(cache.get("a:7d:steam").await, Some(5));
+        assert_eq!(cache.invalidate_tagged(&tags(&["period:7d"])).await, 2);
+        assert!(cache.get("a:7d:steam").await.is_none());
+    }
+}
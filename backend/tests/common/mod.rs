@@ -1,6 +1,10 @@
 // Common test utilities
+#![allow(dead_code)]
+
 use std::sync::Arc;
-use pubg_tracker_api::db::MongoDb;
+use pubg_tracker_api::{
+    db::MongoDb, models::PubgMatchResponse, services::stats_service::DEFAULT_MAX_STALENESS_HOURS,
+};
 
 pub async fn setup_test_mongodb() -> Arc<MongoDb> {
     let mongo_uri = std::env::var("TEST_MONGODB_URI")
@@ -17,7 +21,15 @@ pub async fn cleanup_test_data(db: &MongoDb) {
     // Clean up test data after tests
     db.players().drop(None).await.ok();
     db.stats().drop(None).await.ok();
-    db.create_indexes().await.ok();
+    db.matches().drop(None).await.ok();
+    db.season_stats().drop(None).await.ok();
+    db.ranked_stats().drop(None).await.ok();
+    db.landings().drop(None).await.ok();
+    db.kills().drop(None).await.ok();
+    db.kill_matches().drop(None).await.ok();
+    db.match_weapons().drop(None).await.ok();
+    let stats_retention = std::time::Duration::from_secs(DEFAULT_MAX_STALENESS_HOURS as u64 * 3600);
+    db.create_indexes(stats_retention).await.ok();
 }
 
 #[cfg(test)]
@@ -93,3 +105,74 @@ pub fn mock_pubg_match_response() -> &'static str {
         ]
     }"#
 }
+
+fn participant(id: &str, account_id: &str, kills: i32, win_place: i32) -> serde_json::Value {
+    serde_json::json!({
+        "type": "participant",
+        "id": id,
+        "attributes": {
+            "actor": "",
+            "shardId": "steam",
+            "stats": {
+                "DBNOs": 0, "assists": 0, "boosts": 0, "damageDealt": 100.0 * kills as f64,
+                "deathType": "byplayer", "headshotKills": 0, "heals": 0, "killPlace": 10,
+                "killStreaks": 0, "kills": kills, "longestKill": 0, "name": account_id,
+                "playerId": account_id, "revives": 0, "rideDistance": 0, "roadKills": 0,
+                "swimDistance": 0, "teamKills": 0, "timeSurvived": 600, "vehicleDestroys": 0,
+                "walkDistance": 0, "weaponsAcquired": 0, "winPlace": win_place
+            }
+        }
+    })
+}
+
+fn roster(id: &str, rank: i32, participant_ids: &[&str]) -> serde_json::Value {
+    let data: Vec<serde_json::Value> = participant_ids
+        .iter()
+        .map(|p| serde_json::json!({ "type": "participant", "id": p }))
+        .collect();
+
+    serde_json::json!({
+        "type": "roster",
+        "id": id,
+        "attributes": {
+            "shardId": "steam",
+            "won": if rank == 1 { "true" } else { "false" },
+            "stats": { "rank": rank, "teamId": rank }
+        },
+        "relationships": { "participants": { "data": data } }
+    })
+}
+
+// `teams` lists, for each roster, its rank and (account id, kills) of its members
+pub fn mock_match(match_id: &str, teams: &[(i32, &[(&str, i32)])]) -> PubgMatchResponse {
+    let mut included = Vec::new();
+    for (rank, members) in teams {
+        let ids: Vec<String> = members
+            .iter()
+            .map(|(account_id, _)| format!("{}-{}", match_id, account_id))
+            .collect();
+        for ((account_id, kills), id) in members.iter().zip(&ids) {
+            included.push(participant(id, account_id, *kills, *rank));
+        }
+        let refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+        included.push(roster(&format!("{}-roster{}", match_id, rank), *rank, &refs));
+    }
+
+    serde_json::from_value(serde_json::json!({
+        "data": {
+            "type": "match",
+            "id": match_id,
+            "attributes": {
+                "createdAt": chrono::Utc::now().to_rfc3339(),
+                "duration": 1800,
+                "gameMode": "squad-fpp",
+                "mapName": "Baltic_Main",
+                "isCustomMatch": false,
+                "shardId": "steam",
+                "titleId": "bluehole-pubg"
+            }
+        },
+        "included": included
+    }))
+    .unwrap()
+}
@@ -1,13 +1,5 @@
 #[cfg(test)]
 mod integration_tests {
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-    };
-    use http_body_util::BodyExt;
-    use serde_json::{json, Value};
-    use tower::ServiceExt;
-
     // Helper to create test app
     // This would require exposing a test setup function in main.rs or creating a separate module
     
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod landing_tests {
+    use chrono::Utc;
+    use pubg_tracker_api::models::{
+        landing::{map_size, LandingLocation},
+        telemetry::TelemetryLocation,
+        LandingRecord, MapHeatmap, PubgMatchResponse,
+    };
+    use std::collections::HashMap;
+
+    fn mock_match() -> PubgMatchResponse {
+        serde_json::from_value(serde_json::json!({
+            "data": {
+                "type": "match",
+                "id": "m1",
+                "attributes": {
+                    "createdAt": "2024-01-15T10:00:00Z",
+                    "duration": 1800,
+                    "gameMode": "squad-fpp",
+                    "mapName": "Savage_Main",
+                    "isCustomMatch": false,
+                    "shardId": "steam",
+                    "titleId": "bluehole-pubg"
+                }
+            },
+            "included": [{
+                "type": "participant",
+                "id": "p1",
+                "attributes": {
+                    "actor": "",
+                    "shardId": "steam",
+                    "stats": {
+                        "DBNOs": 0, "assists": 0, "boosts": 0, "damageDealt": 0,
+                        "deathType": "byplayer", "headshotKills": 0, "heals": 0, "killPlace": 10,
+                        "killStreaks": 0, "kills": 0, "longestKill": 0, "name": "a",
+                        "playerId": "account.a", "revives": 0, "rideDistance": 0, "roadKills": 0,
+                        "swimDistance": 0, "teamKills": 0, "timeSurvived": 420,
+                        "vehicleDestroys": 0, "walkDistance": 0, "weaponsAcquired": 0,
+                        "winPlace": 7
+                    }
+                }
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn landing(match_id: &str, account_id: &str, x: f64, y: f64, placement: i32) -> LandingRecord {
+        LandingRecord {
+            id: LandingRecord::key(match_id, account_id),
+            match_id: match_id.to_string(),
+            account_id: account_id.to_string(),
+            shard: "steam".to_string(),
+            map_name: "Baltic_Main".to_string(),
+            game_mode: "squad-fpp".to_string(),
+            created_at: Utc::now(),
+            location: Some(LandingLocation { x, y }),
+            placement,
+            time_survived: 100.0 * (30 - placement) as f64,
+        }
+    }
+
+    #[test]
+    fn test_landing_record_from_match() {
+        let match_data = mock_match();
+        let location = TelemetryLocation {
+            x: 123_400.0,
+            y: 56_700.0,
+            z: 0.0,
+        };
+
+        let record = LandingRecord::new(&match_data, "account.a", Some(&location)).unwrap();
+        assert_eq!(record.id, "m1:account.a");
+        assert_eq!(record.map_name, "Savage_Main");
+        assert_eq!(record.placement, 7);
+        assert_eq!(record.time_survived, 420.0);
+        let location = record.location.unwrap();
+        assert_eq!((location.x, location.y), (1234.0, 567.0)); // cm converted to meters
+
+        assert!(LandingRecord::new(&match_data, "account.b", None).is_none());
+    }
+
+    #[test]
+    fn test_heatmap_cells() {
+        let records = vec![
+            landing("m1", "account.a", 1200.0, 3400.0, 1),
+            landing("m1", "account.b", 1300.0, 3450.0, 1),
+            landing("m2", "account.a", 1450.0, 3100.0, 20),
+            landing("m3", "account.a", 6000.0, 6000.0, 10),
+            // Outside of the map, ignored
+            landing("m4", "account.a", 9000.0, 100.0, 5),
+        ];
+        let names = HashMap::from([
+            ("account.a".to_string(), "Alice".to_string()),
+            ("account.b".to_string(), "Bob".to_string()),
+        ]);
+
+        let maps = MapHeatmap::build(&records, &names, 500.0);
+        assert_eq!(maps.len(), 1);
+        let map = &maps[0];
+        assert_eq!(map.map_name, "Baltic_Main");
+        assert_eq!(map.columns, 17);
+        assert_eq!(map.landings, 4);
+
+        let hot = &map.cells[0];
+        assert_eq!((hot.column, hot.row), (2, 6));
+        assert_eq!((hot.x, hot.y), (1000.0, 3000.0));
+        assert_eq!(hot.landings, 3);
+        assert_eq!(hot.matches, 2);
+        assert_eq!(hot.wins, 2);
+        assert!((hot.avg_placement - 22.0 / 3.0).abs() < 1e-9);
+        assert_eq!(hot.players, vec!["Alice", "Bob"]);
+
+        assert_eq!(map.cells[1].landings, 1);
+        assert_eq!(map.cells[1].avg_placement, 10.0);
+    }
+
+    #[test]
+    fn test_unknown_maps_are_ignored() {
+        let mut record = landing("m1", "account.a", 100.0, 100.0, 1);
+        record.map_name = "Unknown_Main".to_string();
+
+        assert!(map_size("Unknown_Main").is_none());
+        assert!(MapHeatmap::build(&[record], &HashMap::new(), 500.0).is_empty());
+    }
+}
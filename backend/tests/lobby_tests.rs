@@ -0,0 +1,67 @@
+mod common;
+
+#[cfg(test)]
+mod lobby_tests {
+    use crate::common::mock_match;
+    use bson::oid::ObjectId;
+    use pubg_tracker_api::models::{
+        stats::LobbyPerformance, MatchHistoryEntry, PlayerStats, PubgMatchResponse,
+    };
+
+    // Damage is 100 per kill and every participant survives 600 seconds
+    fn mixed_lobby() -> PubgMatchResponse {
+        mock_match(
+            "m1",
+            &[
+                (1, &[("account.a", 4), ("ai.1", 0)]),
+                (2, &[("account.b", 4), ("account.c", 2)]),
+                (3, &[("account.d", 6)]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_lobby_performance() {
+        let lobby = LobbyPerformance::new(&mixed_lobby(), "account.a").unwrap();
+        assert_eq!(lobby.avg_kills, 16.0 / 5.0);
+        assert_eq!(lobby.avg_damage, 320.0);
+        assert_eq!(lobby.avg_survival_time, 600.0);
+        // Better than ai.1 and account.c, tied with account.b, behind account.d
+        assert_eq!(lobby.kill_percentile, 62.5);
+        assert_eq!(lobby.damage_percentile, 62.5);
+        // Everybody tied
+        assert_eq!(lobby.survival_percentile, 50.0);
+
+        assert!(LobbyPerformance::new(&mixed_lobby(), "account.z").is_none());
+    }
+
+    #[test]
+    fn test_relative_performance_averages() {
+        let first = mixed_lobby();
+        let second = mock_match("m2", &[(1, &[("account.a", 3)]), (2, &[("account.b", 1)])]);
+
+        let mut stats = PlayerStats::new(
+            ObjectId::new(),
+            "7d".to_string(),
+            "all".to_string(),
+            "steam".to_string(),
+            0,
+        );
+        for match_data in [&first, &second] {
+            stats.add_match(match_data.participant_stats("account.a").unwrap());
+            stats.add_performance(&LobbyPerformance::new(match_data, "account.a").unwrap());
+        }
+
+        assert_eq!(stats.avg_kill_percentile, (62.5 + 100.0) / 2.0);
+        assert_eq!(stats.avg_damage_percentile, (62.5 + 100.0) / 2.0);
+        assert_eq!(stats.avg_lobby_kills, (3.2 + 2.0) / 2.0);
+        assert_eq!(stats.avg_lobby_survival_time, 600.0);
+    }
+
+    #[test]
+    fn test_match_history_lobby() {
+        let entry = MatchHistoryEntry::from_match(&mixed_lobby(), "account.d").unwrap();
+        assert_eq!(entry.lobby.kill_percentile, 100.0);
+        assert_eq!(entry.lobby.avg_damage, 320.0);
+    }
+}
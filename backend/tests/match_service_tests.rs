@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod match_service_tests {
+    use mockito::Server;
+    use mongodb::bson::doc;
+    use pubg_tracker_api::{
+        db::MongoDb,
+        models::{
+            is_valid_match_id, is_valid_shard, MatchDetailResponse, MatchHistoryEntry,
+            MatchRecord, PubgMatchIncluded, PubgMatchResponse,
+        },
+        services::{MatchService, PubgApiService},
+    };
+    use std::{collections::HashMap, sync::Arc};
+
+    fn mock_match_json(match_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "data": {
+                "type": "match",
+                "id": match_id,
+                "attributes": {
+                    "createdAt": "2024-01-15T10:00:00Z",
+                    "duration": 1800,
+                    "gameMode": "squad-fpp",
+                    "mapName": "Baltic_Main",
+                    "isCustomMatch": false,
+                    "matchType": "official",
+                    "shardId": "steam",
+                    "titleId": "bluehole-pubg"
+                }
+            },
+            "included": [
+                {
+                    "type": "participant",
+                    "id": "participant1",
+                    "attributes": {
+                        "actor": "",
+                        "shardId": "steam",
+                        "stats": {
+                            "DBNOs": 1, "assists": 2, "boosts": 3, "damageDealt": 750.5,
+                            "deathType": "byplayer", "headshotKills": 2, "heals": 5,
+                            "killPlace": 3, "killStreaks": 2, "kills": 5, "longestKill": 150,
+                            "name": "TestPlayer", "playerId": "account.test123", "revives": 0,
+                            "rideDistance": 1000, "roadKills": 0, "swimDistance": 0,
+                            "teamKills": 0, "timeSurvived": 1500, "vehicleDestroys": 0,
+                            "walkDistance": 2500.5, "weaponsAcquired": 5, "winPlace": 1
+                        }
+                    }
+                },
+                {
+                    "type": "roster",
+                    "id": "roster1",
+                    "attributes": {
+                        "shardId": "steam",
+                        "won": "true",
+                        "stats": { "rank": 1, "teamId": 7 }
+                    },
+                    "relationships": {
+                        "participants": {
+                            "data": [{ "type": "participant", "id": "participant1" }]
+                        },
+                        "team": { "data": null }
+                    }
+                },
+                {
+                    "type": "asset",
+                    "id": "asset1",
+                    "attributes": { "URL": "https://telemetry-cdn.pubg.com/telemetry.json" }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_match_record_round_trip() {
+        let raw = mock_match_json("match1");
+        let parsed: PubgMatchResponse = serde_json::from_value(raw.clone()).unwrap();
+
+        let record = MatchRecord::new("steam", &raw, &parsed).unwrap();
+        assert_eq!(record.id, "match1");
+        assert_eq!(record.game_mode, "squad-fpp");
+        assert_eq!(record.participant_ids, vec!["account.test123".to_string()]);
+        assert_eq!(record.created_at.to_rfc3339(), "2024-01-15T10:00:00+00:00");
+
+        // Unmodeled fields are kept in the stored document
+        assert!(record.data.get_document("data").unwrap().contains_key("id"));
+
+        let restored = record.to_response().unwrap();
+        assert_eq!(restored.data.attributes.map_name, "Baltic_Main");
+        match &restored.included[0] {
+            PubgMatchIncluded::Participant { attributes, .. } => {
+                assert_eq!(attributes.stats.kills, 5);
+                assert_eq!(attributes.stats.longest_kill, 150.0);
+            }
+            other => panic!("Expected a participant, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_history_entry_from_match() {
+        let parsed: PubgMatchResponse = serde_json::from_value(mock_match_json("match1")).unwrap();
+
+        let entry = MatchHistoryEntry::from_match(&parsed, "account.test123").unwrap();
+        assert_eq!(entry.match_id, "match1");
+        assert_eq!(entry.map_name, "Baltic_Main");
+        assert_eq!(entry.duration, 1800);
+        assert_eq!(entry.placement, 1);
+        assert_eq!(entry.kills, 5);
+        assert_eq!(entry.death_type, "byplayer");
+
+        assert!(MatchHistoryEntry::from_match(&parsed, "account.other").is_none());
+    }
+
+    #[test]
+    fn test_match_id_and_shard_validation() {
+        assert!(is_valid_match_id("2f3b6a1c-9d4e-4f5a-8b7c-0e1d2c3b4a59"));
+        assert!(!is_valid_match_id(""));
+        assert!(!is_valid_match_id("../players"));
+        assert!(!is_valid_match_id("match1?x=1"));
+
+        assert!(is_valid_shard("steam"));
+        assert!(!is_valid_shard("steam/players"));
+        assert!(!is_valid_shard("pc-eu"));
+    }
+
+    #[test]
+    fn test_match_detail_rosters() {
+        let parsed: PubgMatchResponse = serde_json::from_value(mock_match_json("match1")).unwrap();
+
+        let teams = parsed.teams();
+        assert_eq!(teams.len(), 1);
+        assert_eq!(teams[0].team_id, 7);
+        assert!(teams[0].won);
+        assert_eq!(teams[0].members[0].name, "TestPlayer");
+        assert_eq!(parsed.team_of("account.test123").unwrap().roster_id, "roster1");
+        assert_eq!(
+            parsed.telemetry_url(),
+            Some("https://telemetry-cdn.pubg.com/telemetry.json")
+        );
+
+        let tracked = HashMap::from([(
+            "account.test123".to_string(),
+            "507f1f77bcf86cd799439011".to_string(),
+        )]);
+        let detail = MatchDetailResponse::from_match(&parsed, &tracked);
+        assert_eq!(detail.match_type.as_deref(), Some("official"));
+        assert_eq!(detail.rosters.len(), 1);
+        assert!(detail.rosters[0].has_tracked_player);
+
+        let participant = &detail.rosters[0].participants[0];
+        assert!(participant.tracked);
+        assert_eq!(participant.player_id.as_deref(), Some("507f1f77bcf86cd799439011"));
+        assert_eq!(participant.kills, 5);
+
+        let untracked = MatchDetailResponse::from_match(&parsed, &HashMap::new());
+        assert!(!untracked.rosters[0].has_tracked_player);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_matches_are_fetched_once() {
+        let mongo_uri = std::env::var("TEST_MONGODB_URI")
+            .unwrap_or_else(|_| "mongodb://localhost:27017/pubg-tracker-test".to_string());
+        let db = Arc::new(MongoDb::new(&mongo_uri).await.expect("Failed to connect to test MongoDB"));
+        db.matches().delete_many(doc! { "_id": "match-once" }, None).await.ok();
+
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/steam/matches/match-once")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(mock_match_json("match-once").to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let pubg_api = Arc::new(PubgApiService::new("test-api-key".to_string(), server.url()));
+        let service = MatchService::new(db.clone(), pubg_api);
+        let ids = vec!["match-once".to_string()];
+
+        let first = service.get_matches("steam", &ids).await.unwrap();
+        let second = service.get_matches("steam", &ids).await.unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].data.id, "match-once");
+
+        db.matches().delete_many(doc! { "_id": "match-once" }, None).await.ok();
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod player_tests {
+    use pubg_tracker_api::models::Player;
+
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_matches_keeps_older_ids() {
+        let mut player = Player::new("account.1".into(), "Player".into(), "steam".into());
+
+        player.merge_matches(ids(&["m3", "m2", "m1"]));
+        assert_eq!(player.history(), ids(&["m3", "m2", "m1"]).as_slice());
+
+        // m1 fell out of PUBG's window, m4 and m5 are new
+        player.merge_matches(ids(&["m5", "m4", "m3", "m2"]));
+
+        assert_eq!(player.last_matches, ids(&["m5", "m4", "m3", "m2"]));
+        assert_eq!(player.history(), ids(&["m5", "m4", "m3", "m2", "m1"]).as_slice());
+    }
+
+    #[test]
+    fn test_history_falls_back_to_last_matches() {
+        let mut player = Player::new("account.1".into(), "Player".into(), "steam".into());
+        player.last_matches = ids(&["m2", "m1"]);
+
+        assert_eq!(player.history(), ids(&["m2", "m1"]).as_slice());
+
+        player.merge_matches(ids(&["m3"]));
+        assert_eq!(player.match_history, ids(&["m3", "m2", "m1"]));
+    }
+}
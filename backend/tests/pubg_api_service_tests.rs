@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod pubg_api_service_tests {
-    use mockito::{Mock, Server};
+    use mockito::Server;
     use pubg_tracker_api::services::PubgApiService;
 
     #[tokio::test]
@@ -48,6 +48,47 @@ mod pubg_api_service_tests {
         assert_eq!(player_response.data[0].relationships.matches.data.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_get_player_by_id_success() {
+        let mut server = Server::new_async().await;
+
+        let mock_response = r#"{
+            "data": [{
+                "type": "player",
+                "id": "account.test123",
+                "attributes": {
+                    "name": "RenamedPlayer",
+                    "shardId": "steam"
+                },
+                "relationships": {
+                    "matches": {
+                        "data": [{"type": "match", "id": "match1"}]
+                    }
+                }
+            }]
+        }"#;
+
+        let _mock = server
+            .mock("GET", "/steam/players?filter[playerIds]=account.test123")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(mock_response)
+            .create_async()
+            .await;
+
+        let service = PubgApiService::new(
+            "test-api-key".to_string(),
+            server.url(),
+        );
+
+        let player_response = service
+            .get_player_by_id("steam", "account.test123")
+            .await
+            .unwrap();
+        assert_eq!(player_response.data[0].id, "account.test123");
+        assert_eq!(player_response.data[0].attributes.name, "RenamedPlayer");
+    }
+
     #[tokio::test]
     async fn test_get_player_by_name_not_found() {
         let mut server = Server::new_async().await;
@@ -163,4 +204,30 @@ mod pubg_api_service_tests {
         
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_match_requests_skip_rate_limiter() {
+        let mut server = Server::new_async().await;
+
+        let _mock = server
+            .mock("GET", mockito::Matcher::Regex(r"^/steam/matches/m\d$".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data":{}}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        // One request per minute: a rate limited second request would wait a minute
+        let service = PubgApiService::with_rate_limit("test-api-key".to_string(), server.url(), 1);
+
+        let requests = futures::future::join_all(
+            ["m1", "m2", "m3"].map(|id| service.get_match_raw("steam", id)),
+        );
+        let results = tokio::time::timeout(std::time::Duration::from_secs(5), requests)
+            .await
+            .expect("match requests waited for the rate limiter");
+
+        assert!(results.iter().all(|r| r.is_ok()));
+    }
 }
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod rate_limiter_tests {
+    use pubg_tracker_api::utils::{rate_limiter::parse_reset_header, RateLimiter};
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    };
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_burst_then_throttle() {
+        // 120 requests/minute: a burst of 120, then one token every 500ms
+        let limiter = RateLimiter::new(120);
+        let start = Instant::now();
+
+        for _ in 0..120 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_sync_blocks_until_reset() {
+        let limiter = RateLimiter::new(600);
+        limiter.sync(Some(0), Some(Duration::from_millis(300)));
+
+        let start = Instant::now();
+        limiter.acquire().await;
+
+        assert!(start.elapsed() >= Duration::from_millis(250));
+    }
+
+    #[tokio::test]
+    async fn test_callers_are_served_in_order() {
+        let limiter = Arc::new(RateLimiter::new(600));
+        limiter.block_for(Duration::from_millis(100));
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+
+        for i in 0..5 {
+            let limiter = limiter.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                limiter.acquire().await;
+                order.lock().await.push(i);
+            }));
+            // Make sure each task is queued before the next one is spawned
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().await, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parse_reset_header() {
+        assert_eq!(parse_reset_header("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_reset_header("soon"), None);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let delay = parse_reset_header(&(now + 30).to_string()).unwrap();
+        assert!(delay <= Duration::from_secs(30) && delay >= Duration::from_secs(28));
+    }
+}
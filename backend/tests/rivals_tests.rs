@@ -0,0 +1,192 @@
+mod common;
+
+#[cfg(test)]
+mod rivals_tests {
+    use crate::common::setup_test_mongodb;
+    use chrono::{Duration, TimeZone, Utc};
+    use mongodb::bson::doc;
+    use pubg_tracker_api::{
+        db::KillRepository,
+        models::{
+            rivals::KillParticipant, KillRecord, MatchRecord, PubgMatchResponse, Rivals,
+            TelemetryEvent,
+        },
+    };
+    use std::collections::HashMap;
+
+    fn participant(name: &str, team_id: i32) -> KillParticipant {
+        KillParticipant {
+            account_id: format!("account.{}", name),
+            name: name.to_string(),
+            team_id,
+        }
+    }
+
+    // `day` orders the encounters
+    fn kill(
+        match_id: &str,
+        day: i64,
+        killer: (&str, i32),
+        victim: (&str, i32),
+        weapon: &str,
+    ) -> KillRecord {
+        KillRecord {
+            id: format!("{}:{}", match_id, victim.0),
+            match_id: match_id.to_string(),
+            shard: "steam".to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap() + Duration::days(day),
+            map_name: "Baltic_Main".to_string(),
+            game_mode: "squad-fpp".to_string(),
+            killer: Some(participant(killer.0, killer.1)),
+            victim: participant(victim.0, victim.1),
+            weapon: weapon.to_string(),
+            damage_reason: "TorsoShot".to_string(),
+            distance: 50.0,
+            is_suicide: false,
+        }
+    }
+
+    #[test]
+    fn test_nemeses_and_victims() {
+        let kills = vec![
+            kill("m1", 1, ("x", 2), ("a", 1), "WeapAKM_C"),
+            kill("m2", 2, ("x", 2), ("a", 1), "WeapKar98k_C"),
+            kill("m3", 3, ("x", 2), ("a", 1), "WeapAKM_C"),
+            kill("m4", 4, ("y", 3), ("a", 1), "WeapM416_C"),
+            kill("m1", 1, ("a", 1), ("z", 4), "WeapHK416_C"),
+            kill("m2", 2, ("a", 1), ("y", 3), "WeapHK416_C"),
+            // Team kill and a kill without the player, ignored
+            kill("m5", 5, ("b", 1), ("a", 1), "WeapHK416_C"),
+            kill("m5", 5, ("x", 2), ("y", 3), "WeapHK416_C"),
+        ];
+
+        let rivals = Rivals::compute("account.a", &kills, &HashMap::new(), 10);
+
+        assert_eq!(rivals.nemeses.len(), 2);
+        let nemesis = &rivals.nemeses[0];
+        assert_eq!(nemesis.name, "x");
+        assert_eq!(nemesis.kills, 3);
+        assert_eq!(nemesis.last_weapon, "WeapAKM_C");
+        assert_eq!(nemesis.top_weapon, "WeapAKM_C");
+        assert_eq!(nemesis.last_encounter, "2024-01-04T12:00:00Z");
+        assert!(!nemesis.tracked);
+
+        // Same count, most recent first
+        let victims: Vec<&str> = rivals.victims.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(victims, vec!["y", "z"]);
+        assert!(rivals.head_to_head.is_empty());
+
+        let limited = Rivals::compute("account.a", &kills, &HashMap::new(), 1);
+        assert_eq!(limited.nemeses.len(), 1);
+    }
+
+    #[test]
+    fn test_head_to_head_between_tracked_players() {
+        let kills = vec![
+            kill("m1", 1, ("a", 1), ("b", 2), "WeapHK416_C"),
+            kill("m2", 2, ("b", 5), ("a", 6), "WeapAKM_C"),
+            kill("m3", 3, ("a", 1), ("b", 2), "WeapHK416_C"),
+        ];
+        let tracked = HashMap::from([("account.b".to_string(), "player_b".to_string())]);
+
+        let rivals = Rivals::compute("account.a", &kills, &tracked, 10);
+
+        assert_eq!(rivals.head_to_head.len(), 1);
+        let h2h = &rivals.head_to_head[0];
+        assert_eq!(h2h.player_id, "player_b");
+        assert_eq!((h2h.kills, h2h.deaths), (2, 1));
+        assert_eq!(h2h.last_encounter, "2024-01-04T12:00:00Z");
+        assert_eq!(rivals.victims[0].player_id.as_deref(), Some("player_b"));
+        assert!(rivals.nemeses[0].tracked);
+    }
+
+    #[test]
+    fn test_kill_record_from_telemetry() {
+        let match_data: PubgMatchResponse = serde_json::from_value(serde_json::json!({
+            "data": {
+                "type": "match", "id": "m1",
+                "attributes": {
+                    "createdAt": "2024-01-15T10:00:00Z", "duration": 1800,
+                    "gameMode": "squad-fpp", "mapName": "Baltic_Main", "isCustomMatch": false,
+                    "shardId": "steam", "titleId": "bluehole-pubg"
+                }
+            },
+            "included": []
+        }))
+        .unwrap();
+        let raw = serde_json::to_value(&match_data).unwrap();
+        let header = MatchRecord::new("steam", &raw, &match_data).unwrap();
+
+        let character = |name: &str, team_id: i32| {
+            serde_json::json!({
+                "name": name, "teamId": team_id, "health": 0,
+                "accountId": format!("account.{}", name),
+                "location": { "x": 0, "y": 0, "z": 0 }
+            })
+        };
+        let event: TelemetryEvent = serde_json::from_value(serde_json::json!({
+            "_T": "LogPlayerKillV2", "_D": "2024-01-15T10:05:01Z", "attackId": 42,
+            "victim": character("b", 2), "dBNOMaker": null, "dBNODamageInfo": null,
+            "finisher": null, "finishDamageInfo": null,
+            "killer": character("a", 1),
+            "killerDamageInfo": { "damageReason": "HeadShot", "damageTypeCategory": "Damage_Gun",
+                "damageCauserName": "WeapKar98k_C", "distance": 25000 },
+            "isSuicide": false
+        }))
+        .unwrap();
+        let TelemetryEvent::LogPlayerKillV2(event) = event else {
+            panic!("not a kill event");
+        };
+
+        let record = KillRecord::new(&header, &event);
+        assert_eq!(record.id, "m1:account.b:42");
+        assert_eq!(record.killer.as_ref().unwrap().account_id, "account.a");
+        assert_eq!(record.weapon, "WeapKar98k_C");
+        assert_eq!(record.distance, 250.0);
+        assert!(record.is_fight());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_match_recorded_once_every_kill_is_saved() {
+        let db = setup_test_mongodb().await;
+        let repo = KillRepository::new(db.kills(), db.kill_matches());
+        let match_ids = vec!["rivals-test-m1".to_string()];
+        db.kills()
+            .delete_many(doc! { "match_id": &match_ids[0] }, None)
+            .await
+            .ok();
+        db.kill_matches()
+            .delete_many(doc! { "_id": &match_ids[0] }, None)
+            .await
+            .ok();
+
+        // Kills left by a save that failed before the marker don't count as recorded
+        let first = kill(&match_ids[0], 0, ("a", 1), ("b", 2), "WeapHK416_C");
+        db.kills().insert_one(&first, None).await.unwrap();
+        assert!(repo.find_recorded_match_ids(&match_ids).await.unwrap().is_empty());
+
+        // Saving again keeps the kills already there and adds the others
+        let kills = vec![first, kill(&match_ids[0], 0, ("a", 1), ("c", 3), "WeapHK416_C")];
+        repo.save_match(&match_ids[0], &kills).await.expect("Failed to save kills");
+        repo.save_match(&match_ids[0], &kills).await.expect("Failed to save kills again");
+
+        assert!(repo.find_recorded_match_ids(&match_ids).await.unwrap().contains(&match_ids[0]));
+        let count = db
+            .kills()
+            .count_documents(doc! { "match_id": &match_ids[0] }, None)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // Cleanup
+        db.kills()
+            .delete_many(doc! { "match_id": &match_ids[0] }, None)
+            .await
+            .ok();
+        db.kill_matches()
+            .delete_many(doc! { "_id": &match_ids[0] }, None)
+            .await
+            .ok();
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod season_service_tests {
+    use chrono::Utc;
+    use mockito::Server;
+    use mongodb::bson;
+    use pubg_tracker_api::{
+        models::{RankedStatsRecord, SeasonStatsRecord, SeasonStatsResponse},
+        services::PubgApiService,
+    };
+
+    const PLAYER_SEASON_JSON: &str = r#"{
+        "data": {
+            "type": "playerSeason",
+            "attributes": {
+                "gameModeStats": {
+                    "squad-fpp": {
+                        "assists": 20, "boosts": 40, "dBNOs": 30, "damageDealt": 12000.5,
+                        "headshotKills": 15, "heals": 60, "kills": 50, "longestKill": 320.5,
+                        "losses": 36, "revives": 8, "roundsPlayed": 40, "timeSurvived": 48000,
+                        "top10s": 18, "wins": 4, "walkDistance": 80000
+                    },
+                    "duo-fpp": {
+                        "kills": 10, "roundsPlayed": 10, "wins": 0, "top10s": 2,
+                        "damageDealt": 2000, "longestKill": 150
+                    },
+                    "solo": { "roundsPlayed": 0 }
+                }
+            },
+            "relationships": {}
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_get_player_season_stats() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/steam/players/account.test123/seasons/division.bro.official.pc-2018-30")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(PLAYER_SEASON_JSON)
+            .create_async()
+            .await;
+
+        let service = PubgApiService::new("test-api-key".to_string(), server.url());
+        let response = service
+            .get_player_season_stats("steam", "account.test123", "division.bro.official.pc-2018-30")
+            .await
+            .unwrap();
+
+        let squad = &response.data.attributes.game_mode_stats["squad-fpp"];
+        assert_eq!(squad.dbnos, 30);
+        assert_eq!(squad.rounds_played, 40);
+        // Fields missing from a mode default to zero
+        assert_eq!(response.data.attributes.game_mode_stats["duo-fpp"].assists, 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_seasons() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/steam/seasons")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"data": [
+                    {"type": "season", "id": "division.bro.official.pc-2018-29",
+                     "attributes": {"isCurrentSeason": false, "isOffseason": false}},
+                    {"type": "season", "id": "division.bro.official.pc-2018-30",
+                     "attributes": {"isCurrentSeason": true, "isOffseason": false}}
+                ]}"#,
+            )
+            .create_async()
+            .await;
+
+        let service = PubgApiService::new("test-api-key".to_string(), server.url());
+        let seasons = service.get_seasons("steam").await.unwrap().data;
+
+        assert_eq!(seasons.len(), 2);
+        assert!(!seasons[0].attributes.is_current_season);
+        assert!(seasons[1].attributes.is_current_season);
+    }
+
+    fn record(expires_at: Option<bson::DateTime>) -> SeasonStatsRecord {
+        let response: serde_json::Value = serde_json::from_str(PLAYER_SEASON_JSON).unwrap();
+
+        SeasonStatsRecord {
+            id: None,
+            account_id: "account.test123".to_string(),
+            shard: "steam".to_string(),
+            season_id: "division.bro.official.pc-2018-30".to_string(),
+            is_current_season: true,
+            game_mode_stats: serde_json::from_value(
+                response["data"]["attributes"]["gameModeStats"].clone(),
+            )
+            .unwrap(),
+            fetched_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_season_stats_response() {
+        let response = SeasonStatsResponse::new("player1".to_string(), &record(None));
+
+        // Modes never played are left out, most played first
+        assert_eq!(response.modes.len(), 2);
+        assert_eq!(response.modes[0].mode, "squad-fpp");
+        assert_eq!(response.modes[0].kd_ratio, 50.0 / 36.0);
+        assert_eq!(response.modes[0].win_rate, 10.0);
+        assert_eq!(response.modes[0].top10_rate, 45.0);
+        assert_eq!(response.modes[0].headshot_rate, 30.0);
+
+        assert_eq!(response.overall.mode, "all");
+        assert_eq!(response.overall.rounds_played, 50);
+        assert_eq!(response.overall.kills, 60);
+        assert_eq!(response.overall.longest_kill, 320.5);
+        assert_eq!(response.overall.damage_per_match, 14000.5 / 50.0);
+    }
+
+    #[test]
+    fn test_season_stats_freshness() {
+        let now = Utc::now();
+        let past = bson::DateTime::from_chrono(now - chrono::Duration::hours(1));
+        let future = bson::DateTime::from_chrono(now + chrono::Duration::hours(1));
+
+        // Past seasons have no expiry
+        assert!(record(None).is_fresh(now));
+        assert!(record(Some(future)).is_fresh(now));
+        assert!(!record(Some(past)).is_fresh(now));
+    }
+
+    #[tokio::test]
+    async fn test_get_player_ranked_stats() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock(
+                "GET",
+                "/steam/players/account.test123/seasons/division.bro.official.pc-2018-30/ranked",
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"data": {
+                    "type": "rankedplayerstats",
+                    "attributes": {"rankedGameModeStats": {
+                        "squad-fpp": {
+                            "currentTier": {"tier": "Gold", "subTier": "2"},
+                            "currentRankPoint": 2345,
+                            "bestTier": {"tier": "Platinum", "subTier": "5"},
+                            "bestRankPoint": 2610,
+                            "roundsPlayed": 40, "avgRank": 8.5, "top10Ratio": 0.55,
+                            "winRatio": 0.1, "kda": 1.8, "kills": 45, "deaths": 36,
+                            "assists": 20, "wins": 4, "damageDealt": 10000
+                        },
+                        "squad": {
+                            "currentTier": {"tier": "Silver", "subTier": "1"},
+                            "currentRankPoint": 1900, "roundsPlayed": 5
+                        }
+                    }},
+                    "relationships": {}
+                }}"#,
+            )
+            .create_async()
+            .await;
+
+        let service = PubgApiService::new("test-api-key".to_string(), server.url());
+        let response = service
+            .get_player_ranked_stats("steam", "account.test123", "division.bro.official.pc-2018-30")
+            .await
+            .unwrap();
+
+        let record = RankedStatsRecord {
+            id: None,
+            account_id: "account.test123".to_string(),
+            shard: "steam".to_string(),
+            season_id: "division.bro.official.pc-2018-30".to_string(),
+            is_current_season: true,
+            ranked_game_mode_stats: response.data.attributes.ranked_game_mode_stats,
+            fetched_at: Utc::now(),
+            expires_at: None,
+        };
+
+        let modes = record.modes();
+        assert_eq!(modes.len(), 2);
+
+        // The most played ranked mode is the player's standing
+        let main = record.main_mode().unwrap();
+        assert_eq!(main.mode, "squad-fpp");
+        assert_eq!(main.tier, "Gold");
+        assert_eq!(main.sub_tier, "2");
+        assert_eq!(main.rank_points, 2345);
+        assert_eq!(main.best_tier, "Platinum");
+        assert_eq!(main.win_rate, 10.0);
+        assert_eq!(main.damage_per_match, 250.0);
+    }
+}
@@ -4,9 +4,9 @@ mod stats_service_tests {
     use chrono::Utc;
     use mongodb::bson::doc;
     use pubg_tracker_api::{
-        db::MongoDb,
-        models::{PlayerStats, PubgMatchResponse},
-        services::{StatsService, PubgApiService},
+        db::{MongoDb, PlayerRepository, StatsRepository},
+        models::{ModeFilter, Player, PlayerStats, PubgParticipantStats, StatsPeriod},
+        services::{MatchService, PubgApiService, StatsService},
     };
     use std::sync::Arc;
 
@@ -37,12 +37,13 @@ mod stats_service_tests {
     async fn test_cache_operations() {
         let db = setup_test_db().await;
         let pubg_api = setup_test_pubg_api();
-        let service = StatsService::new(db.clone(), pubg_api);
+        let match_service = Arc::new(MatchService::new(db.clone(), pubg_api));
+        let service = StatsService::new(db.clone(), match_service);
         let player_id = ObjectId::new();
 
         // Test cache miss - should return empty stats
         let result = service
-            .get_or_compute_stats(&player_id, "7d", "solo", "steam")
+            .get_or_compute_stats(&player_id, &StatsPeriod::Days(7), &ModeFilter::parse("solo", None).unwrap(), "steam")
             .await;
         
         assert!(result.is_ok());
@@ -51,11 +52,6 @@ mod stats_service_tests {
 
         // Create and save stats
         let test_stats = PlayerStats {
-            id: None,
-            player_id: player_id.clone(),
-            period: "7d".to_string(),
-            mode: "solo".to_string(),
-            shard: "steam".to_string(),
             kills: 10,
             deaths: 5,
             kd_ratio: 2.0,
@@ -64,15 +60,14 @@ mod stats_service_tests {
             survival_time: 1800.0,
             top1_count: 2,
             matches_played: 10,
-            computed_at: Utc::now(),
-            expires_at: Utc::now() + chrono::Duration::hours(1),
+            ..PlayerStats::new(player_id, "7d".to_string(), "solo".to_string(), "steam".to_string(), 1)
         };
 
         service.save_stats(&test_stats).await.expect("Failed to save stats");
 
         // Test cache hit from memory
         let result2 = service
-            .get_or_compute_stats(&player_id, "7d", "solo", "steam")
+            .get_or_compute_stats(&player_id, &StatsPeriod::Days(7), &ModeFilter::parse("solo", None).unwrap(), "steam")
             .await;
         
         assert!(result2.is_ok());
@@ -86,7 +81,7 @@ mod stats_service_tests {
 
         // After invalidation, should get from DB
         let result3 = service
-            .get_or_compute_stats(&player_id, "7d", "solo", "steam")
+            .get_or_compute_stats(&player_id, &StatsPeriod::Days(7), &ModeFilter::parse("solo", None).unwrap(), "steam")
             .await;
         
         assert!(result3.is_ok());
@@ -105,21 +100,141 @@ mod stats_service_tests {
         // For now, we'll test it in integration tests with real match data
     }
 
+    fn participant(kills: i32, headshots: i32, damage: f64, win_place: i32) -> PubgParticipantStats {
+        serde_json::from_value(serde_json::json!({
+            "DBNOs": 1, "assists": 1, "boosts": 2, "damageDealt": damage,
+            "deathType": if win_place == 1 { "alive" } else { "byplayer" },
+            "headshotKills": headshots, "heals": 3, "killPlace": 10, "killStreaks": 1,
+            "kills": kills, "longestKill": 100.0 * kills as f64, "name": "TestPlayer",
+            "playerId": "account.test", "revives": 0, "rideDistance": 1000.0, "roadKills": 0,
+            "swimDistance": 0.0, "teamKills": 0, "timeSurvived": 1200.0, "vehicleDestroys": 0,
+            "walkDistance": 2000.0, "weaponsAcquired": 4, "winPlace": win_place
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_player_stats_aggregation() {
+        let mut stats =
+            PlayerStats::new(ObjectId::new(), "7d".to_string(), "all".to_string(), "steam".to_string(), 1);
+
+        stats.add_match(&participant(4, 2, 400.0, 1));
+        stats.add_match(&participant(2, 0, 200.0, 8));
+        stats.add_match(&participant(0, 0, 50.0, 30));
+        stats.add_match(&participant(2, 1, 150.0, 15));
+        stats.compute_rates();
+
+        assert_eq!(stats.matches_played, 4);
+        assert_eq!(stats.kills, 8);
+        assert_eq!(stats.deaths, 3);
+        assert_eq!(stats.top1_count, 1);
+        assert_eq!(stats.top10_count, 2);
+        assert_eq!(stats.assists, 4);
+        assert_eq!(stats.longest_kill, 400.0);
+        assert!((stats.kd_ratio - 8.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.headshot_rate, 37.5);
+        assert_eq!(stats.top10_rate, 50.0);
+        assert_eq!(stats.win_rate, 25.0);
+        assert_eq!(stats.damage_per_match, 200.0);
+        assert_eq!(stats.avg_kills, 2.0);
+        assert_eq!(stats.avg_placement, 13.5);
+        assert_eq!(stats.avg_distance, 3000.0);
+    }
+
+    #[test]
+    fn test_stats_period_parse() {
+        assert_eq!(StatsPeriod::parse(None, None, None).unwrap(), StatsPeriod::Days(7));
+        assert_eq!(StatsPeriod::parse(Some("90d"), None, None).unwrap().label(), "90d");
+        assert!(StatsPeriod::parse(Some("14d"), None, None).is_err());
+
+        let range = StatsPeriod::parse(None, Some("2024-01-01T00:00:00Z"), Some("1704672000")).unwrap();
+        assert_eq!(range.label(), "range:1704067200:1704672000");
+
+        let open = StatsPeriod::parse(None, Some("1704067200"), None).unwrap();
+        assert_eq!(open.label(), "range:1704067200:now");
+
+        assert!(StatsPeriod::parse(Some("7d"), Some("1704067200"), None).is_err());
+        assert!(StatsPeriod::parse(None, None, Some("1704067200")).is_err());
+        assert!(StatsPeriod::parse(None, Some("1704672000"), Some("1704067200")).is_err());
+        assert!(StatsPeriod::parse(None, Some("last patch"), None).is_err());
+    }
+
+    #[test]
+    fn test_stats_period_ttl() {
+        let now = Utc::now();
+        let past = StatsPeriod::Range {
+            from: now - chrono::Duration::days(10),
+            to: Some(now - chrono::Duration::days(3)),
+        };
+        let ongoing = StatsPeriod::Range {
+            from: now - chrono::Duration::days(1),
+            to: None,
+        };
+
+        assert_eq!(StatsPeriod::Days(7).ttl(now), chrono::Duration::hours(24));
+        assert_eq!(past.ttl(now), chrono::Duration::days(7));
+        assert_eq!(ongoing.ttl(now), chrono::Duration::hours(1));
+        assert_eq!(ongoing.bounds(now), (now - chrono::Duration::days(1), now));
+    }
+
+    #[test]
+    fn test_mode_filter_parse() {
+        assert_eq!(ModeFilter::parse("all", None).unwrap().label(), "all");
+        assert_eq!(ModeFilter::parse("squad", Some("fpp")).unwrap().label(), "squad-fpp");
+        assert_eq!(ModeFilter::parse("duo-tpp", None).unwrap().label(), "duo-tpp");
+        assert_eq!(ModeFilter::parse("all", Some("tpp")).unwrap().label(), "all-tpp");
+        assert_eq!(ModeFilter::parse("squad-fpp", Some("all")).unwrap().label(), "squad-fpp");
+
+        assert!(ModeFilter::parse("quad", None).is_err());
+        assert!(ModeFilter::parse("squad", Some("vr")).is_err());
+        assert!(ModeFilter::parse("squad-fpp", Some("tpp")).is_err());
+    }
+
+    #[test]
+    fn test_mode_filter_matches() {
+        let squad_fpp = ModeFilter::parse("squad-fpp", None).unwrap();
+        assert!(squad_fpp.matches("squad-fpp"));
+        assert!(squad_fpp.matches("normal-squad-fpp"));
+        assert!(!squad_fpp.matches("squad"));
+        assert!(!squad_fpp.matches("duo-fpp"));
+
+        let duo = ModeFilter::parse("duo", None).unwrap();
+        assert!(duo.matches("duo"));
+        assert!(duo.matches("duo-fpp"));
+        assert!(!duo.matches("solo"));
+
+        let tpp = ModeFilter::parse("all", Some("tpp")).unwrap();
+        assert!(tpp.matches("solo"));
+        assert!(!tpp.matches("solo-fpp"));
+
+        assert!(ModeFilter::all().matches("tdm"));
+    }
+
+    #[test]
+    fn test_mode_filter_game_mode_pattern() {
+        let pattern = |mode: &str, perspective: Option<&str>| {
+            ModeFilter::parse(mode, perspective).unwrap().game_mode_pattern()
+        };
+
+        assert_eq!(pattern("squad-fpp", None).as_deref(), Some("^(normal-)?squad-fpp$"));
+        assert_eq!(pattern("duo", Some("tpp")).as_deref(), Some("^(normal-)?duo(-tpp)?$"));
+        assert_eq!(pattern("solo", None).as_deref(), Some("^(normal-)?solo(-fpp|-tpp)?$"));
+        assert_eq!(pattern("all", Some("fpp")).as_deref(), Some("-fpp$"));
+        assert_eq!(pattern("all", Some("tpp")).as_deref(), Some("^(?!.*-fpp$)"));
+        assert_eq!(ModeFilter::all().game_mode_pattern(), None);
+    }
+
     #[tokio::test]
     #[ignore] // Requires MongoDB running
     async fn test_stats_ttl_expiration() {
         let db = setup_test_db().await;
         let pubg_api = setup_test_pubg_api();
-        let service = StatsService::new(db.clone(), pubg_api);
+        let match_service = Arc::new(MatchService::new(db.clone(), pubg_api));
+        let service = StatsService::new(db.clone(), match_service);
         let player_id = ObjectId::new();
 
         // Create stats that expire in the past
         let expired_stats = PlayerStats {
-            id: None,
-            player_id: player_id.clone(),
-            period: "7d".to_string(),
-            mode: "solo".to_string(),
-            shard: "steam".to_string(),
             kills: 5,
             deaths: 3,
             kd_ratio: 1.67,
@@ -130,6 +245,7 @@ mod stats_service_tests {
             matches_played: 5,
             computed_at: Utc::now() - chrono::Duration::hours(2),
             expires_at: Utc::now() - chrono::Duration::hours(1), // Expired
+            ..PlayerStats::new(player_id, "7d".to_string(), "solo".to_string(), "steam".to_string(), 0)
         };
 
         service.save_stats(&expired_stats).await.expect("Failed to save stats");
@@ -143,4 +259,208 @@ mod stats_service_tests {
             .await
             .ok();
     }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_concurrent_requests_are_coalesced() {
+        let db = setup_test_db().await;
+        let pubg_api = setup_test_pubg_api();
+        let match_service = Arc::new(MatchService::new(db.clone(), pubg_api));
+        let service = StatsService::new(db.clone(), match_service);
+
+        let player = PlayerRepository::new(db.players())
+            .create(Player::new(
+                "account.coalesce".to_string(),
+                "Coalesce".to_string(),
+                "steam".to_string(),
+            ))
+            .await
+            .expect("Failed to create player");
+        let player_id = player.id.unwrap();
+
+        let period = StatsPeriod::Days(7);
+        let mode = ModeFilter::all();
+        let results = futures::future::join_all(
+            (0..5).map(|_| service.get_or_compute_stats(&player_id, &period, &mode, "steam")),
+        )
+        .await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        let metrics = service.metrics.snapshot();
+        assert_eq!(metrics.computations, 1);
+        assert_eq!(metrics.deduplicated, 4);
+
+        // Cleanup
+        db.players().delete_one(doc! { "_id": player_id }, None).await.ok();
+        db.stats()
+            .delete_many(doc! { "player_id": player_id }, None)
+            .await
+            .ok();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_expired_stats_are_served_stale() {
+        let db = setup_test_db().await;
+        let pubg_api = setup_test_pubg_api();
+        let match_service = Arc::new(MatchService::new(db.clone(), pubg_api));
+        let service = StatsService::new(db.clone(), match_service.clone());
+        let player_id = ObjectId::new();
+        let period = StatsPeriod::Days(7);
+        let mode = ModeFilter::parse("solo", None).unwrap();
+
+        let expired_stats = PlayerStats {
+            kills: 5,
+            matches_played: 5,
+            computed_at: Utc::now() - chrono::Duration::hours(2),
+            expires_at: Utc::now() - chrono::Duration::hours(1),
+            ..PlayerStats::new(player_id, "7d".to_string(), "solo".to_string(), "steam".to_string(), 0)
+        };
+        service.save_stats(&expired_stats).await.expect("Failed to save stats");
+
+        let stats = service
+            .get_or_compute_stats(&player_id, &period, &mode, "steam")
+            .await
+            .expect("Expired stats should be served");
+        assert!(stats.stale);
+        assert_eq!(stats.kills, 5);
+        assert_eq!(service.metrics.snapshot().stale_served, 1);
+
+        // Past the maximum staleness the caller waits for a recomputation, which fails here
+        // because the player does not exist
+        let strict = StatsService::new(db.clone(), match_service)
+            .with_max_staleness(chrono::Duration::minutes(30));
+        assert!(strict
+            .get_or_compute_stats(&player_id, &period, &mode, "steam")
+            .await
+            .is_err());
+
+        // Cleanup
+        db.stats()
+            .delete_many(doc! { "player_id": player_id }, None)
+            .await
+            .ok();
+    }
+
+    #[test]
+    fn test_stats_bson_dates() {
+        let mut stats = PlayerStats::new(
+            ObjectId::new(),
+            "7d".to_string(),
+            "all".to_string(),
+            "steam".to_string(),
+            24,
+        );
+        stats.stale = true;
+
+        let document = bson::to_document(&stats).unwrap();
+        // Dates must be BSON dates for the TTL index
+        assert!(matches!(document.get("expires_at"), Some(bson::Bson::DateTime(_))));
+        assert!(matches!(document.get("computed_at"), Some(bson::Bson::DateTime(_))));
+        assert!(!document.contains_key("stale"));
+
+        let decoded: PlayerStats = bson::from_document(document).unwrap();
+        assert_eq!(decoded.expires_at.timestamp_millis(), stats.expires_at.timestamp_millis());
+        assert!(!decoded.stale);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_upsert_inserts_then_updates() {
+        let db = setup_test_db().await;
+        let repo = StatsRepository::new(db.stats());
+        let player_id = ObjectId::new();
+
+        let mut stats = PlayerStats {
+            kills: 3,
+            ..PlayerStats::new(player_id, "30d".to_string(), "squad".to_string(), "steam".to_string(), 1)
+        };
+        repo.upsert(stats.clone()).await.expect("Failed to insert stats");
+
+        let stored = repo
+            .find_by_player(&player_id, "30d", "squad", "steam")
+            .await
+            .unwrap()
+            .expect("Stats should be inserted on a fresh key");
+        assert!(stored.id.is_some());
+        assert_eq!(stored.kills, 3);
+
+        stats.kills = 8;
+        repo.upsert(stats).await.expect("Failed to update stats");
+
+        let updated = repo
+            .find_by_player(&player_id, "30d", "squad", "steam")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.id, stored.id);
+        assert_eq!(updated.kills, 8);
+        let count = db
+            .stats()
+            .count_documents(doc! { "player_id": player_id }, None)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Cleanup
+        db.stats()
+            .delete_many(doc! { "player_id": player_id }, None)
+            .await
+            .ok();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires MongoDB running
+    async fn test_stale_window_survives_a_cache_miss() {
+        let db = setup_test_db().await;
+        let retention = std::time::Duration::from_secs(24 * 3600);
+        db.create_indexes(retention).await.expect("Failed to create indexes");
+
+        // The TTL index keeps expired stats for the staleness window
+        use futures::stream::TryStreamExt;
+        let indexes: Vec<_> = db
+            .stats()
+            .list_indexes(None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let ttl = indexes
+            .iter()
+            .find(|index| {
+                index.options.as_ref().and_then(|o| o.name.as_deref()) == Some("stats_ttl")
+            })
+            .expect("stats_ttl index should exist");
+        assert_eq!(ttl.options.as_ref().unwrap().expire_after, Some(retention));
+
+        // Stored straight in MongoDB, the in-memory cache of a new service knows nothing
+        let player_id = ObjectId::new();
+        let expired_stats = PlayerStats {
+            kills: 4,
+            matches_played: 2,
+            computed_at: Utc::now() - chrono::Duration::hours(3),
+            expires_at: Utc::now() - chrono::Duration::hours(2),
+            ..PlayerStats::new(player_id, "7d".to_string(), "solo".to_string(), "steam".to_string(), 0)
+        };
+        StatsRepository::new(db.stats())
+            .upsert(expired_stats)
+            .await
+            .expect("Failed to save stats");
+
+        let match_service = Arc::new(MatchService::new(db.clone(), setup_test_pubg_api()));
+        let service = StatsService::new(db.clone(), match_service);
+        let stats = service
+            .get_or_compute_stats(&player_id, &StatsPeriod::Days(7), &ModeFilter::parse("solo", None).unwrap(), "steam")
+            .await
+            .expect("Expired stats should be served from MongoDB");
+        assert!(stats.stale);
+        assert_eq!(stats.kills, 4);
+
+        // Cleanup
+        db.stats()
+            .delete_many(doc! { "player_id": player_id }, None)
+            .await
+            .ok();
+    }
 }
@@ -0,0 +1,115 @@
+mod common;
+
+#[cfg(test)]
+mod synergy_tests {
+    use crate::common::mock_match;
+    use pubg_tracker_api::models::{ModeFilter, StatsPeriod, SynergyMember, SynergyStats, Teammate};
+
+    fn member(name: &str) -> SynergyMember {
+        SynergyMember {
+            player_id: format!("id-{}", name),
+            name: name.to_string(),
+            account_id: format!("account.{}", name),
+        }
+    }
+
+    #[test]
+    fn test_synergy_together_and_apart() {
+        let members = vec![member("a"), member("b"), member("c")];
+        let matches = vec![
+            // a and b win together, c plays elsewhere in the same lobby
+            mock_match("m1", &[
+                (1, &[("account.a", 3), ("account.b", 2), ("account.x", 1)]),
+                (5, &[("account.c", 1)]),
+            ]),
+            // a, b and c together
+            mock_match("m2", &[(3, &[("account.a", 1), ("account.b", 0), ("account.c", 2)])]),
+            // a alone
+            mock_match("m3", &[(10, &[("account.a", 4)])]),
+        ];
+
+        // Duplicates (the same match found in several histories) are counted once
+        let mut with_duplicates = matches.clone();
+        with_duplicates.push(matches[0].clone());
+
+        let period = StatsPeriod::parse(Some("7d"), None, None).unwrap();
+        let stats =
+            SynergyStats::compute(&members, &with_duplicates, &period, &ModeFilter::all());
+
+        assert_eq!(stats.matches_together, 2);
+        assert_eq!(stats.wins_together, 1);
+        assert_eq!(stats.win_rate_together, 50.0);
+        assert_eq!(stats.avg_team_placement, 2.0);
+        // Only tracked players count towards the combined kills
+        assert_eq!(stats.combined_kills, 8);
+        assert_eq!(stats.avg_combined_kills, 4.0);
+
+        let a = &stats.players[0];
+        assert_eq!(a.matches_together, 2);
+        assert_eq!(a.avg_kills_together, 2.0);
+        assert_eq!(a.matches_apart, 1);
+        assert_eq!(a.avg_placement_apart, 10.0);
+        assert_eq!(a.win_rate_apart, 0.0);
+
+        let c = &stats.players[2];
+        assert_eq!(c.matches_together, 1);
+        assert_eq!(c.matches_apart, 1);
+
+        assert_eq!(stats.pairs.len(), 3);
+        assert_eq!(stats.pairs[0].player_ids, ["id-a".to_string(), "id-b".to_string()]);
+        assert_eq!(stats.pairs[0].matches_together, 2);
+        assert_eq!(stats.pairs[0].win_rate_together, 50.0);
+    }
+
+    #[test]
+    fn test_synergy_mode_filter() {
+        let members = vec![member("a"), member("b")];
+        let matches = vec![mock_match("m1", &[(1, &[("account.a", 1), ("account.b", 1)])])];
+        let period = StatsPeriod::parse(None, None, None).unwrap();
+
+        let solo = ModeFilter::parse("solo", None).unwrap();
+        let stats = SynergyStats::compute(&members, &matches, &period, &solo);
+        assert_eq!(stats.matches_together, 0);
+        assert!(stats.pairs.is_empty());
+    }
+
+    #[test]
+    fn test_teammates_from_matches() {
+        let matches = vec![
+            mock_match("m1", &[
+                (1, &[("account.a", 3), ("account.b", 2)]),
+                (2, &[("account.x", 6)]),
+            ]),
+            mock_match("m2", &[(4, &[("account.a", 1), ("account.b", 4), ("account.c", 0)])]),
+        ];
+        let period = StatsPeriod::parse(Some("30d"), None, None).unwrap();
+
+        let teammates = Teammate::from_matches("account.a", &matches, &period, &ModeFilter::all());
+        assert_eq!(teammates.len(), 2);
+
+        // Opponents of the lobby are not teammates
+        assert!(teammates.iter().all(|t| t.account_id != "account.x"));
+
+        let b = &teammates[0];
+        assert_eq!(b.account_id, "account.b");
+        assert_eq!(b.name, "account.b");
+        assert_eq!(b.matches_together, 2);
+        assert_eq!(b.wins_together, 1);
+        assert_eq!(b.avg_kills, 3.0);
+        assert_eq!(b.avg_team_placement, 2.5);
+        assert!(!b.tracked);
+
+        assert_eq!(teammates[1].account_id, "account.c");
+        assert_eq!(teammates[1].matches_together, 1);
+    }
+
+    #[test]
+    fn test_bots_are_not_teammates() {
+        let matches = vec![mock_match("m1", &[(2, &[("account.a", 1), ("ai.7", 0), ("account.b", 2)])])];
+        let period = StatsPeriod::parse(Some("30d"), None, None).unwrap();
+
+        let teammates = Teammate::from_matches("account.a", &matches, &period, &ModeFilter::all());
+        let ids: Vec<&str> = teammates.iter().map(|t| t.account_id.as_str()).collect();
+        assert_eq!(ids, vec!["account.b"]);
+    }
+}
@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod telemetry_tests {
+    use flate2::{write::GzEncoder, Compression};
+    use mockito::Server;
+    use pubg_tracker_api::{
+        models::TelemetryEvent,
+        services::{
+            telemetry_service::{read_events, remove_expired_files},
+            PubgApiService,
+        },
+    };
+    use std::io::Write;
+
+    fn character(name: &str, x: f64, y: f64) -> serde_json::Value {
+        serde_json::json!({
+            "name": name, "teamId": 1, "health": 100, "ranking": 0,
+            "accountId": format!("account.{}", name),
+            "location": { "x": x, "y": y, "z": 0 },
+            "isInBlueZone": false, "isInRedZone": false, "zone": ["pochinki"]
+        })
+    }
+
+    fn telemetry_json() -> String {
+        serde_json::json!([
+            { "_T": "LogMatchDefinition", "_D": "2024-01-15T10:00:00Z", "MatchId": "m1" },
+            {
+                "_T": "LogParachuteLanding", "_D": "2024-01-15T10:01:30Z",
+                "character": character("a", 120000.0, 340000.0), "distance": 150000
+            },
+            {
+                "_T": "LogItemPickup", "_D": "2024-01-15T10:01:40Z",
+                "character": character("a", 120000.0, 340000.0),
+                "item": { "itemId": "Item_Weapon_HK416_C", "stackCount": 1,
+                          "category": "Weapon", "subCategory": "Main" }
+            },
+            {
+                "_T": "LogPlayerPosition", "_D": "2024-01-15T10:02:00Z",
+                "character": character("a", 121000.0, 341000.0), "vehicle": null,
+                "elapsedTime": 120, "numAlivePlayers": 98
+            },
+            {
+                "_T": "LogPlayerTakeDamage", "_D": "2024-01-15T10:05:00Z", "attackId": 7,
+                "attacker": character("a", 0.0, 0.0), "victim": character("b", 100.0, 0.0),
+                "damageTypeCategory": "Damage_Gun", "damageReason": "HeadShot", "damage": 55.5,
+                "damageCauserName": "WeapHK416_C", "isThroughPenetrableWall": false
+            },
+            {
+                "_T": "LogPlayerKillV2", "_D": "2024-01-15T10:05:01Z", "attackId": 8,
+                "victim": character("b", 100.0, 0.0),
+                "dBNOMaker": null, "dBNODamageInfo": null,
+                "finisher": character("a", 0.0, 0.0),
+                "finishDamageInfo": { "damageReason": "HeadShot",
+                    "damageTypeCategory": "Damage_Gun", "damageCauserName": "WeapHK416_C",
+                    "additionalInfo": [], "distance": 10000, "isThroughPenetrableWall": false },
+                "killer": character("a", 0.0, 0.0),
+                "killerDamageInfo": { "damageReason": "HeadShot",
+                    "damageTypeCategory": "Damage_Gun", "damageCauserName": "WeapHK416_C",
+                    "additionalInfo": [], "distance": 10000, "isThroughPenetrableWall": false },
+                "assists_AccountId": ["account.c"], "teamKillers_AccountId": [],
+                "isSuicide": false
+            },
+            // Missing its character, skipped rather than failing the whole file
+            { "_T": "LogParachuteLanding", "_D": "2024-01-15T10:01:31Z", "distance": 1 },
+            {
+                "_T": "LogMatchEnd", "_D": "2024-01-15T10:30:00Z",
+                "characters": [
+                    { "character": character("a", 0.0, 0.0), "primaryWeaponFirst": "" },
+                    character("b", 0.0, 0.0)
+                ]
+            }
+        ])
+        .to_string()
+    }
+
+    fn collect(bytes: &[u8]) -> Vec<TelemetryEvent> {
+        let mut events = Vec::new();
+        let count = read_events(bytes, |event| events.push(event)).unwrap();
+        assert_eq!(count, events.len());
+        events
+    }
+
+    fn assert_events(events: &[TelemetryEvent]) {
+        assert_eq!(events.len(), 7);
+        assert!(matches!(events[0], TelemetryEvent::Other));
+
+        match &events[1] {
+            TelemetryEvent::LogParachuteLanding(landing) => {
+                assert_eq!(landing.character.account_id, "account.a");
+                assert_eq!(landing.character.location.x, 120000.0);
+            }
+            other => panic!("Expected a landing, got {:?}", other),
+        }
+        match &events[2] {
+            TelemetryEvent::LogItemPickup(pickup) => {
+                assert_eq!(pickup.item.item_id, "Item_Weapon_HK416_C")
+            }
+            other => panic!("Expected an item pickup, got {:?}", other),
+        }
+        match &events[3] {
+            TelemetryEvent::LogPlayerPosition(position) => {
+                assert_eq!(position.num_alive_players, 98)
+            }
+            other => panic!("Expected a position, got {:?}", other),
+        }
+        match &events[4] {
+            TelemetryEvent::LogPlayerTakeDamage(damage) => {
+                assert_eq!(damage.damage, 55.5);
+                assert_eq!(damage.attacker.as_ref().unwrap().name, "a");
+            }
+            other => panic!("Expected damage, got {:?}", other),
+        }
+        match &events[5] {
+            TelemetryEvent::LogPlayerKillV2(kill) => {
+                assert_eq!(kill.killer.as_ref().unwrap().account_id, "account.a");
+                assert_eq!(kill.killer_damage_info.damage_causer_name, "WeapHK416_C");
+                assert_eq!(kill.killer_damage_info.distance, 10000.0);
+                assert!(kill.dbno_maker.is_none());
+                assert_eq!(kill.assists_account_id, vec!["account.c".to_string()]);
+            }
+            other => panic!("Expected a kill, got {:?}", other),
+        }
+        match &events[6] {
+            TelemetryEvent::LogMatchEnd(end) => {
+                let names: Vec<&str> =
+                    end.characters.iter().map(|c| c.character().name.as_str()).collect();
+                assert_eq!(names, vec!["a", "b"]);
+            }
+            other => panic!("Expected the match end, got {:?}", other),
+        }
+        assert_eq!(events[6].timestamp(), Some("2024-01-15T10:30:00Z"));
+    }
+
+    #[test]
+    fn test_read_plain_telemetry() {
+        let events = collect(telemetry_json().as_bytes());
+        assert_events(&events);
+    }
+
+    #[test]
+    fn test_read_gzip_telemetry() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(telemetry_json().as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let events = collect(&compressed);
+        assert_events(&events);
+    }
+
+    #[test]
+    fn test_read_invalid_telemetry() {
+        assert!(read_events(&b"{\"not\": \"an array\"}"[..], |_| {}).is_err());
+        assert!(read_events(&b"[{\"_T\": \"LogMatchEnd\""[..], |_| {}).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_telemetry() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/bluehole-pubg/steam/2024/01/15/telemetry.json")
+            .with_status(200)
+            .with_body(telemetry_json())
+            .create_async()
+            .await;
+
+        let dir = std::env::temp_dir().join(format!("pubg-telemetry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("m1.json");
+
+        let service = PubgApiService::new("test-api-key".to_string(), server.url());
+        let url = format!("{}/bluehole-pubg/steam/2024/01/15/telemetry.json", server.url());
+        let size = service.download_telemetry(&url, &dest).await.unwrap();
+
+        assert_eq!(size, telemetry_json().len() as u64);
+        let events = collect(&std::fs::read(&dest).unwrap());
+        assert_events(&events);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_remove_expired_telemetry() {
+        let dir =
+            std::env::temp_dir().join(format!("pubg-telemetry-expiry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let day = std::time::Duration::from_secs(24 * 3600);
+
+        let files = [
+            ("old.json", 2 * day),
+            ("old.json.0.part", 2 * day),
+            ("new.json", day / 2),
+        ];
+        for (name, age) in files {
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(std::time::SystemTime::now() - age).unwrap();
+        }
+
+        assert_eq!(remove_expired_files(&dir, day).await.unwrap(), 2);
+        assert!(!dir.join("old.json").exists());
+        assert!(!dir.join("old.json.0.part").exists());
+        assert!(dir.join("new.json").exists());
+
+        // Nothing downloaded yet
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(remove_expired_files(&dir, day).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod timeline_tests {
+    use pubg_tracker_api::models::{
+        timeline::TimelineBuilder, TelemetryEvent, TimelineEvent, TimelineEventKind,
+    };
+    use std::collections::HashMap;
+
+    fn character(name: &str, team_id: i32, x: f64, y: f64) -> serde_json::Value {
+        serde_json::json!({
+            "name": name, "teamId": team_id, "health": 100, "ranking": 0,
+            "accountId": format!("account.{}", name),
+            "location": { "x": x, "y": y, "z": 0 }
+        })
+    }
+
+    fn damage_info(weapon: &str, distance: f64) -> serde_json::Value {
+        serde_json::json!({
+            "damageReason": "TorsoShot", "damageTypeCategory": "Damage_Gun",
+            "damageCauserName": weapon, "additionalInfo": [], "distance": distance,
+            "isThroughPenetrableWall": false
+        })
+    }
+
+    fn telemetry() -> Vec<serde_json::Value> {
+        vec![
+            serde_json::json!({ "_T": "LogMatchStart", "_D": "2024-01-15T10:00:00Z",
+                                "mapName": "Baltic_Main" }),
+            serde_json::json!({
+                "_T": "LogGameStatePeriodic", "_D": "2024-01-15T10:02:00Z",
+                "gameState": { "elapsedTime": 120, "numAlivePlayers": 90,
+                    "safetyZonePosition": { "x": 400000, "y": 400000, "z": 0 },
+                    "safetyZoneRadius": 500000,
+                    "poisonGasWarningPosition": { "x": 300000, "y": 350000, "z": 0 },
+                    "poisonGasWarningRadius": 200000 }
+            }),
+            serde_json::json!({ "_T": "LogPhaseChange", "_D": "2024-01-15T10:03:00Z",
+                                "phase": 1, "elapsedTime": 180 }),
+            // Between two untracked players, ignored
+            serde_json::json!({
+                "_T": "LogPlayerMakeGroggy", "_D": "2024-01-15T10:04:00Z", "attackId": 1,
+                "attacker": character("x", 3, 0.0, 0.0), "victim": character("y", 4, 0.0, 0.0),
+                "damageCauserName": "WeapAK47_C", "distance": 1000
+            }),
+            serde_json::json!({
+                "_T": "LogPlayerMakeGroggy", "_D": "2024-01-15T10:05:00Z", "attackId": 2,
+                "attacker": character("a", 1, 10000.0, 20000.0),
+                "victim": character("b", 2, 12000.0, 20000.0),
+                "damageReason": "TorsoShot", "damageTypeCategory": "Damage_Gun",
+                "damageCauserName": "WeapHK416_C", "distance": 2000
+            }),
+            serde_json::json!({
+                "_T": "LogPlayerKillV2", "_D": "2024-01-15T10:05:10Z", "attackId": 3,
+                "victim": character("b", 2, 12000.0, 20000.0),
+                "dBNOMaker": null, "dBNODamageInfo": null,
+                "finisher": character("a", 1, 10000.0, 20000.0),
+                "finishDamageInfo": damage_info("WeapHK416_C", 2000.0),
+                "killer": character("a", 1, 10000.0, 20000.0),
+                "killerDamageInfo": damage_info("WeapHK416_C", 2000.0),
+                "isSuicide": false
+            }),
+            serde_json::json!({
+                "_T": "LogPlayerRevive", "_D": "2024-01-15T10:06:00Z",
+                "reviver": character("c", 1, 0.0, 0.0), "victim": character("a", 1, 0.0, 0.0)
+            }),
+            serde_json::json!({
+                "_T": "LogVehicleDestroy", "_D": "2024-01-15T10:07:00Z", "attackId": 4,
+                "attacker": character("a", 1, 0.0, 0.0),
+                "vehicle": { "vehicleType": "WheeledVehicle", "vehicleId": "Dacia_A_01_v2_C" },
+                "damageTypeCategory": "Damage_Gun", "damageCauserName": "WeapHK416_C",
+                "distance": 5000
+            }),
+        ]
+    }
+
+    fn timeline(tracked: &[(&str, &str)]) -> Vec<TimelineEvent> {
+        let tracked: HashMap<String, String> = tracked
+            .iter()
+            .map(|(account, id)| (account.to_string(), id.to_string()))
+            .collect();
+
+        let mut builder = TimelineBuilder::new(tracked, None);
+        for event in telemetry() {
+            builder.add_event(serde_json::from_value::<TelemetryEvent>(event).unwrap());
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn test_timeline_of_tracked_player() {
+        let events = timeline(&[("account.a", "player_a")]);
+        let kinds: Vec<TimelineEventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TimelineEventKind::PhaseChange,
+                TimelineEventKind::Knock,
+                TimelineEventKind::Kill,
+                TimelineEventKind::Revive,
+                TimelineEventKind::VehicleDestroy,
+            ]
+        );
+
+        let phase = &events[0];
+        assert_eq!(phase.elapsed, 180.0);
+        let zone = phase.zone.as_ref().unwrap();
+        assert_eq!((zone.phase, zone.center.x, zone.center.y), (1, 3000.0, 3500.0));
+        assert_eq!(zone.radius, 2000.0);
+
+        let knock = &events[1];
+        assert_eq!(knock.elapsed, 300.0);
+        let player = knock.player.as_ref().unwrap();
+        assert_eq!(player.player_id.as_deref(), Some("player_a"));
+        assert_eq!((player.position.x, player.position.y), (100.0, 200.0));
+        assert_eq!(knock.target.as_ref().unwrap().player_id, None);
+        assert_eq!(knock.weapon.as_deref(), Some("WeapHK416_C"));
+        assert_eq!(knock.distance, Some(20.0));
+
+        assert_eq!(events[4].vehicle.as_deref(), Some("Dacia_A_01_v2_C"));
+    }
+
+    #[test]
+    fn test_knock_and_kill_between_tracked_players_on_both_sides() {
+        let events = timeline(&[("account.a", "player_a"), ("account.b", "player_b")]);
+        let knock_events: Vec<TimelineEventKind> = events
+            .iter()
+            .filter(|e| e.elapsed == 300.0)
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(knock_events, vec![TimelineEventKind::Knock, TimelineEventKind::Knocked]);
+
+        let kill_events: Vec<TimelineEventKind> = events
+            .iter()
+            .filter(|e| e.elapsed == 310.0)
+            .map(|e| e.kind)
+            .collect();
+
+        assert_eq!(kill_events, vec![TimelineEventKind::Kill, TimelineEventKind::Death]);
+    }
+}
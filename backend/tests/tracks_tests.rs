@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tracks_tests {
+    use pubg_tracker_api::models::{
+        tracks::{delta_decode, delta_encode, TracksBuilder},
+        TelemetryEvent,
+    };
+    use std::collections::HashMap;
+
+    fn position(name: &str, elapsed: f64, x: f64, y: f64) -> TelemetryEvent {
+        serde_json::from_value(serde_json::json!({
+            "_T": "LogPlayerPosition", "_D": "2024-01-15T10:02:00Z",
+            "character": {
+                "name": name, "teamId": 1, "health": 100, "ranking": 0,
+                "accountId": format!("account.{}", name),
+                "location": { "x": x, "y": y, "z": 0 }
+            },
+            "vehicle": null, "elapsedTime": elapsed, "numAlivePlayers": 90
+        }))
+        .unwrap()
+    }
+
+    fn game_state(elapsed: f64, radius: f64) -> TelemetryEvent {
+        serde_json::from_value(serde_json::json!({
+            "_T": "LogGameStatePeriodic", "_D": "2024-01-15T10:02:00Z",
+            "gameState": { "elapsedTime": elapsed, "numAlivePlayers": 90,
+                "safetyZonePosition": { "x": 400000, "y": 400000, "z": 0 },
+                "safetyZoneRadius": radius,
+                "poisonGasWarningPosition": { "x": 300000, "y": 350000, "z": 0 },
+                "poisonGasWarningRadius": 200000,
+                "redZonePosition": { "x": 0, "y": 0, "z": 0 }, "redZoneRadius": 0 }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_delta_encoding_round_trip() {
+        let values = vec![120, 130, 125, 125, 400];
+        let deltas = delta_encode(&values);
+        assert_eq!(deltas, vec![120, 10, -5, 0, 275]);
+        assert_eq!(delta_decode(&deltas), values);
+        assert!(delta_encode(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_tracks_are_down_sampled() {
+        let tracked = HashMap::from([("account.a".to_string(), "player_a".to_string())]);
+        let mut builder = TracksBuilder::new(tracked, 20);
+
+        for event in [
+            position("a", 0.0, 0.0, 0.0), // starting island, ignored
+            position("a", 10.0, 100000.0, 200000.0),
+            position("a", 20.0, 101000.0, 200000.0),
+            position("a", 30.0, 102000.0, 201000.0),
+            position("b", 30.0, 0.0, 0.0), // not tracked
+            position("a", 40.0, 104000.0, 199000.0),
+            game_state(10.0, 500000.0),
+            game_state(15.0, 490000.0),
+            game_state(30.0, 480000.0),
+        ] {
+            builder.add_event(event);
+        }
+
+        let (players, zones) = builder.finish();
+        assert_eq!(players.len(), 1);
+        let track = &players[0];
+        assert_eq!(track.player_id.as_deref(), Some("player_a"));
+        assert_eq!(delta_decode(&track.t), vec![10, 30]);
+        assert_eq!(delta_decode(&track.x), vec![1000, 1020]);
+        assert_eq!(delta_decode(&track.y), vec![2000, 2010]);
+
+        assert_eq!(delta_decode(&zones.t), vec![10, 30]);
+        assert_eq!(delta_decode(&zones.blue_radius), vec![5000, 4800]);
+        assert_eq!(delta_decode(&zones.safe_x), vec![3000, 3000]);
+        assert_eq!(delta_decode(&zones.red_radius), vec![0, 0]);
+    }
+}
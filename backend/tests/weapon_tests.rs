@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod weapon_tests {
+    use pubg_tracker_api::models::{
+        weapons::weapon_name, MatchWeapons, MatchWeaponsRecord, TelemetryEvent, WeaponComparison,
+        WeaponStats,
+    };
+
+    fn character(name: &str, team_id: i32) -> serde_json::Value {
+        serde_json::json!({
+            "name": name, "teamId": team_id, "health": 100, "ranking": 0,
+            "accountId": format!("account.{}", name),
+            "location": { "x": 0, "y": 0, "z": 0 }
+        })
+    }
+
+    fn damage_info(weapon: &str, reason: &str, distance: f64) -> serde_json::Value {
+        serde_json::json!({
+            "damageReason": reason, "damageTypeCategory": "Damage_Gun",
+            "damageCauserName": weapon, "additionalInfo": [], "distance": distance,
+            "isThroughPenetrableWall": false
+        })
+    }
+
+    fn kill(
+        killer: (&str, i32),
+        victim: (&str, i32),
+        weapon: &str,
+        reason: &str,
+        distance: f64,
+    ) -> TelemetryEvent {
+        serde_json::from_value(serde_json::json!({
+            "_T": "LogPlayerKillV2", "_D": "2024-01-15T10:05:01Z", "attackId": 1,
+            "victim": character(victim.0, victim.1),
+            "dBNOMaker": null, "dBNODamageInfo": null,
+            "finisher": character(killer.0, killer.1),
+            "finishDamageInfo": damage_info(weapon, reason, distance),
+            "killer": character(killer.0, killer.1),
+            "killerDamageInfo": damage_info(weapon, reason, distance),
+            "isSuicide": false
+        }))
+        .unwrap()
+    }
+
+    fn knock(attacker: (&str, i32), victim: (&str, i32), weapon: &str) -> TelemetryEvent {
+        serde_json::from_value(serde_json::json!({
+            "_T": "LogPlayerMakeGroggy", "_D": "2024-01-15T10:05:00Z", "attackId": 1,
+            "attacker": character(attacker.0, attacker.1),
+            "victim": character(victim.0, victim.1),
+            "damageReason": "TorsoShot", "damageTypeCategory": "Damage_Gun",
+            "damageCauserName": weapon, "distance": 2500
+        }))
+        .unwrap()
+    }
+
+    fn damage(
+        attacker: (&str, i32),
+        victim: (&str, i32),
+        weapon: &str,
+        amount: f64,
+    ) -> TelemetryEvent {
+        serde_json::from_value(serde_json::json!({
+            "_T": "LogPlayerTakeDamage", "_D": "2024-01-15T10:04:59Z", "attackId": 1,
+            "attacker": character(attacker.0, attacker.1),
+            "victim": character(victim.0, victim.1),
+            "damageTypeCategory": "Damage_Gun", "damageReason": "TorsoShot",
+            "damage": amount, "damageCauserName": weapon
+        }))
+        .unwrap()
+    }
+
+    fn match_weapons(events: &[TelemetryEvent]) -> MatchWeapons {
+        let mut weapons = MatchWeapons::default();
+        for event in events {
+            weapons.add_event(event);
+        }
+        weapons
+    }
+
+    #[test]
+    fn test_weapon_totals_from_events() {
+        let weapons = match_weapons(&[
+            damage(("a", 1), ("b", 2), "WeapHK416_C", 40.0),
+            knock(("a", 1), ("b", 2), "WeapHK416_C"),
+            kill(("a", 1), ("b", 2), "WeapHK416_C", "HeadShot", 10000.0),
+            kill(("a", 1), ("c", 3), "WeapHK416_C", "TorsoShot", 30000.0),
+            kill(("a", 1), ("d", 4), "WeapKar98k_C", "HeadShot", 25000.0),
+        ]);
+
+        let totals = &weapons.players["account.a"];
+        let hk416 = &totals["WeapHK416_C"];
+        assert_eq!(hk416.kills, 2);
+        assert_eq!(hk416.knocks, 1);
+        assert_eq!(hk416.headshot_kills, 1);
+        assert_eq!(hk416.damage_dealt, 40.0);
+        assert_eq!(hk416.kill_distance, 400.0); // cm converted to meters
+        assert_eq!(hk416.longest_kill, 300.0);
+
+        let stats = WeaponStats::from_totals(totals);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].name, "HK416");
+        assert_eq!(stats[0].headshot_rate, 50.0);
+        assert_eq!(stats[0].avg_kill_distance, 200.0);
+        assert_eq!(stats[1].name, "Kar98k");
+        assert_eq!(stats[1].headshot_rate, 100.0);
+    }
+
+    #[test]
+    fn test_team_damage_is_ignored() {
+        let weapons = match_weapons(&[
+            damage(("a", 1), ("b", 1), "WeapHK416_C", 40.0),
+            knock(("a", 1), ("b", 1), "WeapHK416_C"),
+            kill(("a", 1), ("b", 1), "WeapHK416_C", "HeadShot", 1000.0),
+        ]);
+
+        assert!(weapons.players.is_empty());
+    }
+
+    #[test]
+    fn test_match_weapons_record_bson() {
+        let weapons = match_weapons(&[
+            damage(("a", 1), ("b", 2), "WeapHK416_C", 40.0),
+            kill(("a", 1), ("b", 2), "WeapHK416_C", "HeadShot", 10000.0),
+        ]);
+        let record = MatchWeaponsRecord::new("m1", "steam", &weapons);
+
+        let document = mongodb::bson::to_document(&record).unwrap();
+        let decoded: MatchWeaponsRecord = mongodb::bson::from_document(document).unwrap();
+
+        let hk416 = &decoded.weapons_of("account.a").unwrap()["WeapHK416_C"];
+        assert_eq!(hk416.kills, 1);
+        assert_eq!(hk416.damage_dealt, 40.0);
+        assert!(decoded.weapons_of("account.b").is_none());
+
+        // A projection without the player's entry leaves `players` out
+        let decoded: MatchWeaponsRecord =
+            mongodb::bson::from_document(mongodb::bson::doc! { "_id": "m1", "shard": "steam" })
+                .unwrap();
+        assert!(decoded.weapons_of("account.a").is_none());
+    }
+
+    #[test]
+    fn test_weapon_name() {
+        assert_eq!(weapon_name("WeapHK416_C"), "HK416");
+        assert_eq!(weapon_name("ProjGrenade_C"), "ProjGrenade");
+        assert_eq!(weapon_name("PanzerFaust100M"), "PanzerFaust100M");
+    }
+
+    #[test]
+    fn test_weapon_comparison() {
+        let a = match_weapons(&[
+            kill(("a", 1), ("x", 2), "WeapHK416_C", "HeadShot", 1000.0),
+            kill(("a", 1), ("y", 2), "WeapHK416_C", "HeadShot", 1000.0),
+        ]);
+        let b = match_weapons(&[
+            kill(("b", 1), ("x", 2), "WeapHK416_C", "TorsoShot", 1000.0),
+            kill(("b", 1), ("y", 2), "WeapMini14_C", "TorsoShot", 1000.0),
+        ]);
+
+        let players = vec![
+            (
+                "id_a".to_string(),
+                "a".to_string(),
+                WeaponStats::from_totals(&a.players["account.a"]),
+            ),
+            (
+                "id_b".to_string(),
+                "b".to_string(),
+                WeaponStats::from_totals(&b.players["account.b"]),
+            ),
+        ];
+
+        let comparison = WeaponComparison::compare(&players);
+        assert_eq!(comparison.len(), 2);
+        assert_eq!(comparison[0].name, "HK416");
+        assert_eq!(comparison[0].total_kills, 3);
+        assert_eq!(comparison[0].players.len(), 2);
+        assert_eq!(comparison[1].name, "Mini14");
+        assert_eq!(comparison[1].players[0].player_id, "id_b");
+    }
+}
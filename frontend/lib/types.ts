@@ -24,14 +24,83 @@ export interface PlayerStats {
   survival_time: number;
   top1_count: number;
   matches_played: number;
+  // Participant totals
+  assists?: number;
+  dbnos?: number;
+  headshot_kills?: number;
+  revives?: number;
+  heals?: number;
+  boosts?: number;
+  weapons_acquired?: number;
+  walk_distance?: number;
+  ride_distance?: number;
+  swim_distance?: number;
+  longest_kill?: number;
+  top10_count?: number;
+  // Per-match averages
+  avg_kills?: number;
+  avg_assists?: number;
+  avg_dbnos?: number;
+  damage_per_match?: number;
+  avg_survival_time?: number;
+  avg_heals?: number;
+  avg_boosts?: number;
+  avg_distance?: number;
+  avg_placement?: number;
+  avg_kill_place?: number;
+  // Rates, in percent
+  headshot_rate?: number;
+  top10_rate?: number;
+  // Bots vs humans
+  human_kills?: number;
+  bot_kills?: number;
+  deaths_to_bots?: number;
+  bot_headshot_kills?: number;
+  human_kd_ratio?: number;
+  // The bot split is partial when fewer matches have telemetry than have bots
+  matches_with_bots?: number;
+  matches_with_telemetry?: number;
+  avg_humans_in_lobby?: number;
+  avg_bots_in_lobby?: number;
+  avg_human_placement?: number;
+  humans_only?: boolean;
+  stale?: boolean;
+  // Relative to the lobby, percentiles from 0 to 100
+  avg_lobby_kills?: number;
+  avg_lobby_damage?: number;
+  avg_lobby_survival_time?: number;
+  avg_kill_percentile?: number;
+  avg_damage_percentile?: number;
+  avg_survival_percentile?: number;
   computed_at: Date;
 }
 
+export interface RankedModeStats {
+  mode: string;
+  tier: string;
+  sub_tier: string;
+  rank_points: number;
+  best_tier: string;
+  best_sub_tier: string;
+  best_rank_points: number;
+  rounds_played: number;
+  wins: number;
+  kills: number;
+  deaths: number;
+  assists: number;
+  kda: number;
+  win_rate: number;
+  top10_rate: number;
+  avg_rank: number;
+  damage_per_match: number;
+}
+
 export interface DashboardData {
   players: Array<{
     player_id: string;
     name: string;
     stats: PlayerStats;
+    ranked?: RankedModeStats;
   }>;
   period: string;
   mode: string;